@@ -1,5 +1,13 @@
+// The IDL handlers that `#[program]` emits at the crate root still call
+// `AccountInfo::realloc`, so the allow cannot sit on a narrower item.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::system_program;
+use anchor_spl::memo::{self, BuildMemo, Memo};
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
 declare_id!("FundManager11111111111111111111111111111111");
 
@@ -7,99 +15,559 @@ declare_id!("FundManager11111111111111111111111111111111");
 pub mod fund_manager {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, admin: Pubkey) -> Result<()> {
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        fund_id: [u8; 16],
+        admin: Pubkey,
+        recovery_address: Pubkey,
+    ) -> Result<()> {
         let fund_account = &mut ctx.accounts.fund_account;
+        fund_account.fund_id = fund_id;
         fund_account.admin = admin;
+        fund_account.pending_admin = None;
+        fund_account.recovery_address = recovery_address;
+        fund_account.vault = ctx.accounts.fund_token_account.key();
+        fund_account.mint = ctx.accounts.mint.key();
         fund_account.total_funds = 0;
+        fund_account.total_deposited = 0;
+        fund_account.total_allocated = 0;
         fund_account.bump = ctx.bumps.fund_account;
-        fund_account.whitelist_count = 0;
+        fund_account.whitelist_active = 0;
+        fund_account.whitelist_total_created = 0;
+        fund_account.paused = false;
+        fund_account.allocation_cooldown_secs = 0;
+        fund_account.fee_bps = 0;
+        fund_account.fee_destination = Pubkey::default();
+        fund_account.daily_limit = 0;
+        fund_account.window_start = 0;
+        fund_account.allocated_in_window = 0;
+        fund_account.approver = Pubkey::default();
+        fund_account.approval_threshold = 0;
+        fund_account.proposal_ttl_secs = 0;
+        fund_account.proposal_nonce = 0;
+        fund_account.min_allocation = 0;
+        fund_account.co_admins = Vec::new();
+        fund_account.admin_threshold = 1;
+        fund_account.admin_set_version = 0;
+        fund_account.admin_op_nonce = 0;
+        fund_account.tier_limits = [0; MAX_TIERS];
+        fund_account.deposits_locked = false;
+        fund_account.claim_nonce = 0;
+        fund_account.claim_grace_secs = 0;
+        fund_account.total_claimable = 0;
+        fund_account.vesting_nonce = 0;
+        fund_account.recurring_nonce = 0;
+        fund_account.min_deposit = 0;
+        fund_account.audit_enabled = false;
+        fund_account.audit_retention_secs = 0;
+        fund_account.action_sequence = 0;
+        fund_account.admin_change_delay_secs = 0;
+        fund_account.admin_change_effective_at = 0;
+        fund_account.pending_admin_change_delay = 0;
+        fund_account.admin_delay_change_at = 0;
+        fund_account.restricted_deposits = false;
+        fund_account.open_deposits = true;
+        fund_account.max_allocations_per_hour = 0;
+        fund_account.allocation_rate_window_secs = DEFAULT_ALLOCATION_RATE_WINDOW_SECS;
+        fund_account.whitelist_entries = 0;
+        fund_account.open_locks = 0;
+        fund_account.open_recurring = 0;
+        fund_account.escrow_nonce = 0;
+        fund_account.open_escrows = 0;
+        
+        let allocation_log = &mut ctx.accounts.allocation_log;
+        allocation_log.fund = fund_account.key();
+        allocation_log.head = 0;
+        allocation_log.bump = ctx.bumps.allocation_log;
         Ok(())
     }
 
     pub fn store_funds(ctx: Context<StoreFunds>, amount: u64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
         let fund_account = &mut ctx.accounts.fund_account;
         
-        token::transfer(
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        // A min_deposit of 0 allows any non-zero amount
+        require!(
+            amount >= fund_account.min_deposit,
+            FundError::DepositTooSmall
+        );
+        
+        // Deposits are attributed to the source account's owner, so that is
+        // who must be cleared; the entry is ignored when deposits are open
+        let depositor_cleared = ctx
+            .accounts
+            .depositor_entry
+            .as_ref()
+            .is_some_and(|entry| entry.is_active && !entry.suspended && entry.can_deposit);
+        if fund_account.restricted_deposits {
+            require!(depositor_cleared, FundError::DepositorNotWhitelisted);
+        }
+        
+        // Admin-seeded funds: only admins and cleared depositors may deposit
+        if !fund_account.open_deposits {
+            require!(
+                depositor_cleared || is_admin(fund_account, &ctx.accounts.authority.key()),
+                FundError::DepositsRestricted
+            );
+        }
+        
+        token_interface::transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.from_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
                     to: ctx.accounts.fund_token_account.to_account_info(),
                     authority: ctx.accounts.authority.to_account_info(),
                 },
             ),
             amount,
+            ctx.accounts.mint.decimals,
         )?;
 
-        fund_account.total_funds = fund_account.total_funds.checked_add(amount).unwrap();
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.total_deposited = fund_account
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+
+        // Attribute the deposit to the source account's owner, which differs
+        // from the signer when a delegate transfers on the owner's behalf
+        let source_owner = ctx.accounts.from_token_account.owner;
+        let depositor = &mut ctx.accounts.depositor;
+        depositor.fund = fund_account.key();
+        depositor.depositor = source_owner;
+        depositor.total_deposited = depositor
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        depositor.deposit_count = depositor
+            .deposit_count
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        depositor.last_deposit_at = Clock::get()?.unix_timestamp;
+        depositor.bump = ctx.bumps.depositor;
+
+        emit!(FundsStored {
+            depositor: source_owner,
+            authority: ctx.accounts.authority.key(),
+            vault: ctx.accounts.fund_token_account.key(),
+            amount,
+            new_total: fund_account.total_funds,
+            total_deposited: fund_account.total_deposited,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
-    pub fn allocate_funds(ctx: Context<AllocateFunds>, amount: u64) -> Result<()> {
+    pub fn allocate_funds(ctx: Context<AllocateFunds>, amount: u64) -> Result<u64> {
+        allocate(ctx.accounts, &ctx.bumps, amount, String::new(), None, 0, 0)
+    }
+
+    // allocate_funds keeps its original signature for existing callers
+    pub fn allocate_funds_with_memo(
+        ctx: Context<AllocateFunds>,
+        amount: u64,
+        memo: String,
+        reason_code: Option<u16>,
+    ) -> Result<u64> {
+        allocate(ctx.accounts, &ctx.bumps, amount, memo, reason_code, 0, 0)
+    }
+
+    // Guards for relayed transactions: valid_until (0 = no expiry) stops a
+    // stale transaction from landing late, and min_vault_balance_after
+    // (0 = unchecked) lets the caller assert what the vault keeps.
+    pub fn allocate_funds_with_expiry(
+        ctx: Context<AllocateFunds>,
+        amount: u64,
+        valid_until: i64,
+        min_vault_balance_after: u64,
+    ) -> Result<u64> {
+        allocate(
+            ctx.accounts,
+            &ctx.bumps,
+            amount,
+            String::new(),
+            None,
+            valid_until,
+            min_vault_balance_after,
+        )
+    }
+
+    // A nonzero nonce is recorded on-chain so a retried transaction cannot
+    // pay out twice. A zero nonce behaves exactly like allocate_funds.
+    pub fn allocate_funds_with_nonce(
+        ctx: Context<AllocateFundsWithNonce>,
+        amount: u64,
+        allocation_nonce: u64,
+    ) -> Result<u64> {
+        match ctx.accounts.nonce_record.as_mut() {
+            Some(nonce_record) => {
+                require!(allocation_nonce != 0, FundError::InvalidAllocationNonce);
+                // init_if_needed hands back an existing record untouched
+                require!(
+                    nonce_record.created_at == 0,
+                    FundError::DuplicateAllocationNonce
+                );
+                
+                nonce_record.fund = ctx.accounts.allocate.fund_account.key();
+                nonce_record.nonce = allocation_nonce;
+                nonce_record.recipient = ctx.accounts.allocate.whitelist_entry.address;
+                nonce_record.amount = amount;
+                nonce_record.created_at = Clock::get()?.unix_timestamp;
+                nonce_record.payer = ctx.accounts.allocate.authority.key();
+                nonce_record.bump = ctx
+                    .bumps
+                    .nonce_record
+                    .ok_or(error!(FundError::AllocationNonceRequired))?;
+            }
+            None => require!(allocation_nonce == 0, FundError::AllocationNonceRequired),
+        }
+        
+        allocate(
+            &mut ctx.accounts.allocate,
+            &ctx.bumps.allocate,
+            amount,
+            String::new(),
+            None,
+            0,
+            0,
+        )
+    }
+
+    pub fn allocate_funds_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AllocateFundsBatch<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
         let fund_account = &mut ctx.accounts.fund_account;
-        let whitelist_entry = &ctx.accounts.whitelist_entry;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            can_allocate(fund_account, &ctx.accounts.authority, ctx.accounts.operator.as_ref()),
+            FundError::UnauthorizedAllocator
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
         
         require!(
-            ctx.accounts.admin.key() == fund_account.admin,
+            amounts.len() <= MAX_BATCH_SIZE,
+            FundError::BatchTooLarge
+        );
+        
+        // Each recipient supplies a (whitelist_entry, to_token_account) pair
+        require!(
+            ctx.remaining_accounts.len() == amounts.len() * 2,
+            FundError::InvalidBatchAccounts
+        );
+        
+        allocate_to_pairs(ctx.accounts, ctx.remaining_accounts, &ctx.bumps, &amounts)
+    }
+
+    pub fn withdraw_funds(ctx: Context<WithdrawFunds>, amount: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
             FundError::UnauthorizedAdmin
         );
         
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
         require!(
             fund_account.total_funds >= amount,
             FundError::InsufficientFunds
         );
+
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.destination_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+
+        emit!(FundsWithdrawn {
+            admin: ctx.accounts.admin.key(),
+            vault: ctx.accounts.fund_token_account.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            new_total: fund_account.total_funds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::Withdraw,
+            ctx.accounts.admin.key(),
+            ctx.accounts.destination_token_account.owner,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>, amount: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
         
+        // Deliberately not gated on `paused` so the vault can be evacuated mid-incident
         require!(
-            whitelist_entry.is_active,
-            FundError::RecipientNotWhitelisted
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
         );
         
         require!(
-            whitelist_entry.address == ctx.accounts.to_token_account.owner,
-            FundError::WhitelistAddressMismatch
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
         );
 
-        let seeds = &[
-            b"fund_account".as_ref(),
-            &[fund_account.bump],
-        ];
-        let signer = &[&seeds[..]];
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.recovery_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
 
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.fund_token_account.to_account_info(),
-                    to: ctx.accounts.to_token_account.to_account_info(),
-                    authority: fund_account.to_account_info(),
-                },
-                signer,
-            ),
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+
+        emit!(EmergencyWithdrawal {
+            admin: ctx.accounts.admin.key(),
+            vault: ctx.accounts.fund_token_account.key(),
+            recovery_address: fund_account.recovery_address,
+            amount,
+            new_total: fund_account.total_funds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::EmergencyWithdraw,
+            ctx.accounts.admin.key(),
+            fund_account.recovery_address,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn emergency_drain(ctx: Context<EmergencyDrain>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // Requiring a pause first keeps a full drain from happening by accident
+        require!(fund_account.paused, FundError::FundNotPaused);
+        
+        // Claims and vesting schedules are paid from this vault, so they must be
+        // cancelled or revoked before it is emptied
+        require!(
+            fund_account.total_claimable == 0,
+            FundError::ClaimsOutstanding
+        );
+        
+        // Drain the real balance, which also sweeps tokens sent outside store_funds
+        let amount = ctx.accounts.fund_token_account.amount;
+        if amount > 0 {
+            transfer_from_vault(
+                fund_account,
+                &ctx.accounts.fund_token_account,
+                &ctx.accounts.mint,
+                ctx.accounts.recovery_token_account.to_account_info(),
+                &ctx.accounts.token_program,
+                amount,
+            )?;
+        }
+        
+        fund_account.total_funds = 0;
+
+        emit!(EmergencyDrained {
+            fund: fund_account.key(),
+            admin: ctx.accounts.admin.key(),
+            vault: ctx.accounts.fund_token_account.key(),
+            recovery_address: fund_account.recovery_address,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::EmergencyDrain,
+            ctx.accounts.admin.key(),
+            fund_account.recovery_address,
             amount,
         )?;
+        
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+
+        apply_paused(fund_account, paused, ctx.accounts.admin.key())?;
+        let action = if paused { AuditAction::Pause } else { AuditAction::Unpause };
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            action,
+            ctx.accounts.admin.key(),
+            Pubkey::default(),
+            0,
+        )
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.current_admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+
+        schedule_admin_change(fund_account, new_admin, ctx.accounts.current_admin.key())?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ProposeAdmin,
+            ctx.accounts.current_admin.key(),
+            new_admin,
+            0,
+        )?;
+        Ok(())
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        let pending_admin = fund_account
+            .pending_admin
+            .ok_or(FundError::NoPendingAdmin)?;
+        
+        require!(
+            ctx.accounts.new_admin.key() == pending_admin,
+            FundError::UnauthorizedPendingAdmin
+        );
+        
+        // Accepting is the finalize step of the timelock
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= fund_account.admin_change_effective_at,
+            FundError::AdminChangeTimelocked
+        );
 
-        fund_account.total_funds = fund_account.total_funds.checked_sub(amount).unwrap();
+        let previous_admin = std::mem::replace(&mut fund_account.admin, pending_admin);
+        fund_account.pending_admin = None;
+        fund_account.admin_change_effective_at = 0;
+        
+        emit!(AdminChangeFinalized {
+            fund: fund_account.key(),
+            previous_admin,
+            new_admin: pending_admin,
+            timestamp: now,
+        });
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::AcceptAdmin,
+            pending_admin,
+            previous_admin,
+            0,
+        )?;
         Ok(())
     }
 
-    pub fn set_admin(ctx: Context<SetAdmin>, new_admin: Pubkey) -> Result<()> {
+    pub fn cancel_admin_proposal(ctx: Context<CancelAdminProposal>) -> Result<()> {
         let fund_account = &mut ctx.accounts.fund_account;
         
         require!(
-            ctx.accounts.current_admin.key() == fund_account.admin,
+            is_admin(fund_account, &ctx.accounts.current_admin.key()),
             FundError::UnauthorizedAdmin
         );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        let pending_admin = fund_account
+            .pending_admin
+            .ok_or(FundError::NoPendingAdmin)?;
 
-        fund_account.admin = new_admin;
+        fund_account.pending_admin = None;
+        fund_account.admin_change_effective_at = 0;
+        
+        emit!(AdminChangeCancelled {
+            fund: fund_account.key(),
+            cancelled_admin: pending_admin,
+            actor: ctx.accounts.current_admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         Ok(())
     }
 
-    pub fn add_whitelist(ctx: Context<AddWhitelist>, address: Pubkey, label: String) -> Result<()> {
+    pub fn add_whitelist(
+        ctx: Context<AddWhitelist>,
+        address: Pubkey,
+        label: String,
+        max_allocation: u64,
+        unlock_at: i64,
+        expires_at: i64,
+    ) -> Result<()> {
         let fund_account = &mut ctx.accounts.fund_account;
         let whitelist_entry = &mut ctx.accounts.whitelist_entry;
         
         require!(
-            ctx.accounts.admin.key() == fund_account.admin,
+            is_admin(fund_account, &ctx.accounts.admin.key()),
             FundError::UnauthorizedAdmin
         );
         
@@ -108,13 +576,99 @@ pub mod fund_manager {
             FundError::LabelTooLong
         );
         
-        whitelist_entry.address = address;
-        whitelist_entry.label = label;
-        whitelist_entry.is_active = true;
-        whitelist_entry.added_by = ctx.accounts.admin.key();
-        whitelist_entry.added_at = Clock::get()?.unix_timestamp;
+        whitelist_entry.set_inner(new_whitelist_entry(
+            address,
+            TargetKind::Owner,
+            label,
+            fund_account.fund_id,
+            ctx.accounts.admin.key(),
+            Clock::get()?.unix_timestamp,
+        ));
+        whitelist_entry.max_allocation = max_allocation;
+        whitelist_entry.unlock_at = unlock_at;
+        whitelist_entry.expires_at = expires_at;
+        
+        fund_account.whitelist_active = fund_account
+            .whitelist_active
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.whitelist_total_created = fund_account
+            .whitelist_total_created
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.whitelist_entries = fund_account
+            .whitelist_entries
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistAdded,
+            ctx.accounts.admin.key(),
+            address,
+            max_allocation,
+        )?;
+        
+        Ok(())
+    }
+    
+    // Binds the entry to one token account rather than to its owner, for
+    // recipients such as program-controlled vaults whose owner is a PDA
+    pub fn add_whitelist_token_account(
+        ctx: Context<AddWhitelistTokenAccount>,
+        label: String,
+        max_allocation: u64,
+        unlock_at: i64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let address = ctx.accounts.token_account.key();
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            label.len() <= 64,
+            FundError::LabelTooLong
+        );
+        
+        whitelist_entry.set_inner(new_whitelist_entry(
+            address,
+            TargetKind::TokenAccount,
+            label,
+            fund_account.fund_id,
+            ctx.accounts.admin.key(),
+            Clock::get()?.unix_timestamp,
+        ));
+        whitelist_entry.max_allocation = max_allocation;
+        whitelist_entry.unlock_at = unlock_at;
+        whitelist_entry.expires_at = expires_at;
         
-        fund_account.whitelist_count = fund_account.whitelist_count.checked_add(1).unwrap();
+        fund_account.whitelist_active = fund_account
+            .whitelist_active
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.whitelist_total_created = fund_account
+            .whitelist_total_created
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.whitelist_entries = fund_account
+            .whitelist_entries
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistAdded,
+            ctx.accounts.admin.key(),
+            address,
+            max_allocation,
+        )?;
         
         Ok(())
     }
@@ -124,7 +678,7 @@ pub mod fund_manager {
         let whitelist_entry = &mut ctx.accounts.whitelist_entry;
         
         require!(
-            ctx.accounts.admin.key() == fund_account.admin,
+            is_admin(fund_account, &ctx.accounts.admin.key()),
             FundError::UnauthorizedAdmin
         );
         
@@ -135,134 +689,6655 @@ pub mod fund_manager {
         
         whitelist_entry.is_active = false;
         
+        fund_account.whitelist_active = fund_account
+            .whitelist_active
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistRemoved,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
         Ok(())
     }
     
     pub fn toggle_whitelist(ctx: Context<ToggleWhitelist>, is_active: bool) -> Result<()> {
-        let fund_account = &ctx.accounts.fund_account;
+        let fund_account = &mut ctx.accounts.fund_account;
         let whitelist_entry = &mut ctx.accounts.whitelist_entry;
         
         require!(
-            ctx.accounts.admin.key() == fund_account.admin,
+            is_admin(fund_account, &ctx.accounts.admin.key()),
             FundError::UnauthorizedAdmin
         );
         
+        // Only a real transition changes the number of active entries
+        if whitelist_entry.is_active != is_active {
+            fund_account.whitelist_active = if is_active {
+                fund_account
+                    .whitelist_active
+                    .checked_add(1)
+                    .ok_or(error!(FundError::MathOverflow))?
+            } else {
+                fund_account
+                    .whitelist_active
+                    .checked_sub(1)
+                    .ok_or(error!(FundError::CounterUnderflow))?
+            };
+        }
+        
         whitelist_entry.is_active = is_active;
+        let action = if is_active { AuditAction::WhitelistReactivated } else { AuditAction::WhitelistRemoved };
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            action,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
         
         Ok(())
     }
-}
-
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = payer,
-        space = 8 + FundAccount::INIT_SPACE,
-        seeds = [b"fund_account"],
-        bump
-    )]
-    pub fund_account: Account<'info, FundAccount>,
-    
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct StoreFunds<'info> {
-    #[account(mut)]
-    pub fund_account: Account<'info, FundAccount>,
-    
-    #[account(mut)]
-    pub from_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub fund_token_account: Account<'info, TokenAccount>,
-    
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
 
-#[derive(Accounts)]
-pub struct AllocateFunds<'info> {
-    #[account(mut)]
-    pub fund_account: Account<'info, FundAccount>,
-    
-    #[account(mut)]
-    pub fund_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub to_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        seeds = [b"whitelist", to_token_account.owner.as_ref()],
-        bump
-    )]
-    pub whitelist_entry: Account<'info, WhitelistEntry>,
-    
-    pub admin: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
+    pub fn close_whitelist(ctx: Context<CloseWhitelist>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            !whitelist_entry.is_active,
+            FundError::WhitelistEntryStillActive
+        );
+        
+        // The entry already left whitelist_active when it was deactivated
+        fund_account.whitelist_entries = fund_account
+            .whitelist_entries
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistClosed,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct SetAdmin<'info> {
-    #[account(mut)]
-    pub fund_account: Account<'info, FundAccount>,
-    
-    pub current_admin: Signer<'info>,
-}
+    pub fn update_whitelist_label(ctx: Context<UpdateWhitelistLabel>, new_label: String) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            new_label.len() <= 64,
+            FundError::LabelTooLong
+        );
+        
+        let old_label = std::mem::replace(&mut whitelist_entry.label, new_label);
+        
+        emit!(WhitelistLabelUpdated {
+            address: whitelist_entry.address,
+            old_label,
+            new_label: whitelist_entry.label.clone(),
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistLabelUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(address: Pubkey)]
-pub struct AddWhitelist<'info> {
-    #[account(mut)]
-    pub fund_account: Account<'info, FundAccount>,
-    
+    // Unlike toggle_whitelist, a suspension records why and when, and the
+    // entry keeps counting towards whitelist_active
+    pub fn suspend_whitelist(ctx: Context<SuspendWhitelist>, reason: String) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            reason.len() <= 128,
+            FundError::SuspensionReasonTooLong
+        );
+        
+        require!(
+            !whitelist_entry.suspended,
+            FundError::WhitelistAlreadySuspended
+        );
+        
+        whitelist_entry.suspended = true;
+        whitelist_entry.suspension_reason = reason.clone();
+        whitelist_entry.suspended_at = Clock::get()?.unix_timestamp;
+        
+        emit!(WhitelistEntrySuspended {
+            address: whitelist_entry.address,
+            reason,
+            actor: ctx.accounts.admin.key(),
+            timestamp: whitelist_entry.suspended_at,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistSuspended,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn unsuspend_whitelist(ctx: Context<SuspendWhitelist>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            whitelist_entry.suspended,
+            FundError::WhitelistNotSuspended
+        );
+        
+        whitelist_entry.suspended = false;
+        whitelist_entry.suspension_reason = String::new();
+        whitelist_entry.suspended_at = 0;
+        
+        emit!(WhitelistEntryUnsuspended {
+            address: whitelist_entry.address,
+            actor: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistUnsuspended,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn reactivate_whitelist(ctx: Context<ReactivateWhitelist>, label: String) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            !whitelist_entry.is_active,
+            FundError::WhitelistEntryAlreadyActive
+        );
+        
+        require!(
+            label.len() <= 64,
+            FundError::LabelTooLong
+        );
+        
+        whitelist_entry.label = label;
+        whitelist_entry.is_active = true;
+        whitelist_entry.added_by = ctx.accounts.admin.key();
+        whitelist_entry.added_at = Clock::get()?.unix_timestamp;
+        whitelist_entry.fund_id = fund_account.fund_id;
+        
+        fund_account.whitelist_active = fund_account
+            .whitelist_active
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistReactivated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn set_unlock_time(ctx: Context<SetUnlockTime>, unlock_at: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        whitelist_entry.unlock_at = unlock_at;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistUnlockTimeUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn update_allocation_cap(ctx: Context<UpdateAllocationCap>, max_allocation: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_allocation_cap(whitelist_entry, max_allocation);
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistCapUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            max_allocation,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn set_period_budget(
+        ctx: Context<SetPeriodBudget>,
+        period_limit: u64,
+        period_secs: i64,
+    ) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_period_budget(whitelist_entry, period_limit, period_secs)
+    }
+
+    pub fn set_allocation_cooldown(ctx: Context<SetAllocationCooldown>, cooldown_secs: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_allocation_cooldown(fund_account, cooldown_secs)
+    }
+
+    pub fn set_fee(ctx: Context<SetFee>, fee_bps: u16, fee_destination: Pubkey) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_fee(fund_account, fee_bps, fee_destination)
+    }
+
+    pub fn set_daily_limit(ctx: Context<SetDailyLimit>, daily_limit: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_daily_limit(fund_account, daily_limit, ctx.accounts.admin.key())
+    }
+
+    pub fn add_operator(ctx: Context<AddOperator>, operator: Pubkey) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let operator_account = &mut ctx.accounts.operator_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // Re-adding a removed operator reuses its existing PDA
+        operator_account.fund = fund_account.key();
+        operator_account.operator = operator;
+        operator_account.is_active = true;
+        operator_account.added_by = ctx.accounts.admin.key();
+        operator_account.added_at = Clock::get()?.unix_timestamp;
+        operator_account.bump = ctx.bumps.operator_account;
+        
+        emit!(OperatorAdded {
+            fund: fund_account.key(),
+            operator,
+            actor: ctx.accounts.admin.key(),
+            timestamp: operator_account.added_at,
+        });
+        
+        Ok(())
+    }
+
+    pub fn remove_operator(ctx: Context<RemoveOperator>) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let operator_account = &mut ctx.accounts.operator_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        operator_account.is_active = false;
+        
+        emit!(OperatorRemoved {
+            fund: fund_account.key(),
+            operator: operator_account.operator,
+            actor: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_approval_config(
+        ctx: Context<SetApprovalConfig>,
+        approver: Pubkey,
+        approval_threshold: u64,
+        proposal_ttl_secs: i64,
+    ) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // The approver is the second pair of eyes, so it cannot be the admin
+        require!(
+            approval_threshold == 0 || (approver != Pubkey::default() && !is_admin(fund_account, &approver)),
+            FundError::InvalidApprover
+        );
+        
+        require!(
+            proposal_ttl_secs >= 0,
+            FundError::InvalidProposalTtl
+        );
+        
+        fund_account.approver = approver;
+        fund_account.approval_threshold = approval_threshold;
+        fund_account.proposal_ttl_secs = proposal_ttl_secs;
+        
+        Ok(())
+    }
+
+    pub fn propose_allocation(
+        ctx: Context<ProposeAllocation>,
+        amount: u64,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let proposal = &mut ctx.accounts.proposal;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            can_allocate(fund_account, &ctx.accounts.authority, ctx.accounts.operator.as_ref()),
+            FundError::UnauthorizedAllocator
+        );
+        
+        require!(
+            ctx.accounts.whitelist_entry.is_active,
+            FundError::RecipientNotWhitelisted
+        );
+        
+        proposal.fund = fund_account.key();
+        proposal.nonce = fund_account.proposal_nonce;
+        proposal.whitelist_entry = ctx.accounts.whitelist_entry.key();
+        proposal.recipient = recipient;
+        proposal.amount = amount;
+        proposal.proposer = ctx.accounts.authority.key();
+        proposal.created_at = Clock::get()?.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
+        
+        fund_account.proposal_nonce = fund_account
+            .proposal_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        Ok(())
+    }
+
+    pub fn approve_allocation(ctx: Context<ApproveAllocation>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let proposal = &ctx.accounts.proposal;
+        let amount = proposal.amount;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            ctx.accounts.approver.key() == fund_account.approver,
+            FundError::UnauthorizedApprover
+        );
+        
+        require!(
+            ctx.accounts.approver.key() != proposal.proposer,
+            FundError::InvalidApprover
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            !proposal_expired(fund_account, proposal, timestamp),
+            FundError::ProposalExpired
+        );
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        let total_allocated = check_allocation(
+            fund_account,
+            whitelist_entry,
+            &ctx.accounts.to_token_account,
+            &ctx.accounts.fund_token_account,
+            amount,
+            timestamp,
+        )?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.to_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            whitelist_entry.address,
+            amount,
+            timestamp,
+        );
+
+        emit!(FundsAllocated {
+            recipient: whitelist_entry.address,
+            vault: ctx.accounts.fund_token_account.key(),
+            amount,
+            fee,
+            new_total: fund_account.total_funds,
+            total_allocated: fund_account.total_allocated,
+            memo: String::new(),
+            reason_code: None,
+            valid_until: 0,
+            allocation_count: whitelist_entry.allocation_count,
+            timestamp,
+        });
+
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ApproveAllocation,
+            ctx.accounts.approver.key(),
+            whitelist_entry.address,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_allocation(_ctx: Context<CancelAllocation>) -> Result<()> {
+        // Only the proposer can sign (see CancelAllocation), and the proposal
+        // is closed back to them. Expired proposals are cleaned up this way too.
+        Ok(())
+    }
+
+    pub fn set_min_allocation(ctx: Context<SetMinAllocation>, min_allocation: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        fund_account.min_allocation = min_allocation;
+        
+        Ok(())
+    }
+
+    pub fn set_min_deposit(ctx: Context<SetMinDeposit>, min_deposit: u64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        fund_account.min_deposit = min_deposit;
+        
+        Ok(())
+    }
+
+    pub fn add_admin(ctx: Context<AddAdmin>, new_admin: Pubkey) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_admin_action(fund_account, &AdminAction::AddAdmin { admin: new_admin })?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::AddAdmin,
+            ctx.accounts.admin.key(),
+            new_admin,
+            0,
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_admin(ctx: Context<RemoveAdmin>, admin: Pubkey) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_admin_action(fund_account, &AdminAction::RemoveAdmin { admin })?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::RemoveAdmin,
+            ctx.accounts.admin.key(),
+            admin,
+            0,
+        )?;
+        Ok(())
+    }
+
+    pub fn set_admin_threshold(ctx: Context<SetAdminThreshold>, threshold: u8) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        apply_admin_action(fund_account, &AdminAction::SetThreshold { threshold })?;
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::SetThreshold,
+            ctx.accounts.admin.key(),
+            Pubkey::default(),
+            threshold as u64,
+        )
+    }
+
+    pub fn create_admin_operation(ctx: Context<CreateAdminOperation>, action: AdminAction) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let operation = &mut ctx.accounts.operation;
+        
+        let index = admin_index(fund_account, &ctx.accounts.admin.key())
+            .ok_or(FundError::UnauthorizedAdmin)?;
+        
+        // The proposer's signature counts as the first approval
+        operation.fund = fund_account.key();
+        operation.nonce = fund_account.admin_op_nonce;
+        operation.action = action;
+        operation.proposer = ctx.accounts.admin.key();
+        operation.approvals = 1 << index;
+        operation.admin_set_version = fund_account.admin_set_version;
+        operation.created_at = Clock::get()?.unix_timestamp;
+        operation.bump = ctx.bumps.operation;
+        
+        fund_account.admin_op_nonce = fund_account
+            .admin_op_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        Ok(())
+    }
+
+    pub fn approve_admin_operation(ctx: Context<ApproveAdminOperation>) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let operation = &mut ctx.accounts.operation;
+        
+        let index = admin_index(fund_account, &ctx.accounts.admin.key())
+            .ok_or(FundError::UnauthorizedAdmin)?;
+        
+        require!(
+            operation.admin_set_version == fund_account.admin_set_version,
+            FundError::StaleAdminOperation
+        );
+        
+        require!(
+            operation.approvals & (1 << index) == 0,
+            FundError::AdminOperationAlreadyApproved
+        );
+        
+        operation.approvals |= 1 << index;
+        
+        Ok(())
+    }
+
+    pub fn execute_admin_operation(ctx: Context<ExecuteAdminOperation>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let operation = &ctx.accounts.operation;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        check_admin_approvals(fund_account, operation)?;
+        
+        // Recorded before the action applies, so an operation that turns
+        // auditing off is still audited
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ExecuteAdminOperation,
+            ctx.accounts.admin.key(),
+            operation.key(),
+            0,
+        )?;
+        
+        match operation.action {
+            AdminAction::ProposeAdmin { new_admin } => {
+                schedule_admin_change(fund_account, new_admin, ctx.accounts.admin.key())
+            }
+            AdminAction::SetPaused { paused } => {
+                apply_paused(fund_account, paused, ctx.accounts.admin.key())
+            }
+            AdminAction::SetFee { fee_bps, fee_destination } => {
+                apply_fee(fund_account, fee_bps, fee_destination)
+            }
+            AdminAction::SetDailyLimit { daily_limit } => {
+                apply_daily_limit(fund_account, daily_limit, ctx.accounts.admin.key())
+            }
+            AdminAction::SetDepositsLocked { locked } => {
+                fund_account.deposits_locked = locked;
+                Ok(())
+            }
+            AdminAction::SetRestrictedDeposits { restricted } => {
+                fund_account.restricted_deposits = restricted;
+                Ok(())
+            }
+            AdminAction::SetOpenDeposits { open } => {
+                let fund = fund_account.key();
+                apply_open_deposits(fund_account, fund, open, ctx.accounts.admin.key())
+            }
+            AdminAction::SetTierLimits { tier_limits } => {
+                fund_account.tier_limits = tier_limits;
+                Ok(())
+            }
+            AdminAction::SetAllocationCooldown { cooldown_secs } => {
+                apply_allocation_cooldown(fund_account, cooldown_secs)
+            }
+            AdminAction::SetMinAllocation { min_allocation } => {
+                fund_account.min_allocation = min_allocation;
+                Ok(())
+            }
+            AdminAction::SetMinDeposit { min_deposit } => {
+                fund_account.min_deposit = min_deposit;
+                Ok(())
+            }
+            AdminAction::UpdateAllocationCap { address, max_allocation } => {
+                let whitelist_entry =
+                    admin_action_entry(fund_account, ctx.accounts.whitelist_entry.as_mut(), address)?;
+                apply_allocation_cap(whitelist_entry, max_allocation);
+                Ok(())
+            }
+            AdminAction::SetPeriodBudget { address, period_limit, period_secs } => {
+                let whitelist_entry =
+                    admin_action_entry(fund_account, ctx.accounts.whitelist_entry.as_mut(), address)?;
+                apply_period_budget(whitelist_entry, period_limit, period_secs)
+            }
+            AdminAction::SetAuditConfig { enabled, retention_secs } => {
+                apply_audit_config(fund_account, enabled, retention_secs)
+            }
+            AdminAction::Allocate { .. } => err!(FundError::InvalidAdminAction),
+            ref action => apply_admin_action(fund_account, action),
+        }
+    }
+
+    pub fn execute_allocation_operation(ctx: Context<ExecuteAllocationOperation>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let operation = &ctx.accounts.operation;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        check_admin_approvals(fund_account, operation)?;
+        
+        let AdminAction::Allocate { recipient, amount } = operation.action else {
+            return err!(FundError::InvalidAdminAction);
+        };
+        
+        require!(
+            whitelist_entry.address == recipient,
+            FundError::WhitelistAddressMismatch
+        );
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        let total_allocated = check_allocation(
+            fund_account,
+            whitelist_entry,
+            &ctx.accounts.to_token_account,
+            &ctx.accounts.fund_token_account,
+            amount,
+            timestamp,
+        )?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.to_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            whitelist_entry.address,
+            amount,
+            timestamp,
+        );
+
+        emit!(FundsAllocated {
+            recipient: whitelist_entry.address,
+            vault: ctx.accounts.fund_token_account.key(),
+            amount,
+            fee,
+            new_total: fund_account.total_funds,
+            total_allocated: fund_account.total_allocated,
+            memo: String::new(),
+            reason_code: None,
+            valid_until: 0,
+            allocation_count: whitelist_entry.allocation_count,
+            timestamp,
+        });
+
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ExecuteAllocationOperation,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_admin_operation(_ctx: Context<CancelAdminOperation>) -> Result<()> {
+        // Only the proposer can sign (see CancelAdminOperation), and the
+        // operation is closed back to them
+        Ok(())
+    }
+
+    pub fn renew_whitelist(ctx: Context<RenewWhitelist>, new_expires_at: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            new_expires_at == 0 || new_expires_at > Clock::get()?.unix_timestamp,
+            FundError::InvalidExpiry
+        );
+        
+        whitelist_entry.expires_at = new_expires_at;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistRenewed,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn add_whitelist_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddWhitelistBatch<'info>>,
+        entries: Vec<WhitelistBatchEntry>,
+    ) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            entries.len() <= MAX_BATCH_SIZE,
+            FundError::BatchTooLarge
+        );
+        
+        // Each entry supplies its uninitialized whitelist PDA
+        require!(
+            ctx.remaining_accounts.len() == entries.len(),
+            FundError::InvalidBatchAccounts
+        );
+        
+        // Catch duplicates up front rather than failing on the second allocate
+        for (i, entry) in entries.iter().enumerate() {
+            require!(
+                !entries[..i].iter().any(|other| other.address == entry.address),
+                FundError::DuplicateBatchAddress
+            );
+        }
+        
+        let added = entries.len() as u64;
+        let added_by = ctx.accounts.admin.key();
+        let added_at = Clock::get()?.unix_timestamp;
+        let space = 8 + WhitelistEntry::INIT_SPACE;
+        
+        for (entry, account) in entries.into_iter().zip(ctx.remaining_accounts.iter()) {
+            require!(
+                entry.label.len() <= 64,
+                FundError::LabelTooLong
+            );
+            
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"whitelist", fund_account.fund_id.as_ref(), entry.address.as_ref()],
+                &crate::ID,
+            );
+            require!(
+                account.key() == expected,
+                FundError::InvalidBatchAccounts
+            );
+            
+            create_pda_account(
+                ctx.accounts.admin.to_account_info(),
+                account.clone(),
+                ctx.accounts.system_program.to_account_info(),
+                space,
+                &[b"whitelist", fund_account.fund_id.as_ref(), entry.address.as_ref(), &[bump]],
+            )?;
+            
+            let whitelist_entry = new_whitelist_entry(
+                entry.address,
+                TargetKind::Owner,
+                entry.label,
+                fund_account.fund_id,
+                added_by,
+                added_at,
+            );
+            whitelist_entry.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+            
+            fund_account.whitelist_active = fund_account
+                .whitelist_active
+                .checked_add(1)
+                .ok_or(error!(FundError::MathOverflow))?;
+            fund_account.whitelist_total_created = fund_account
+                .whitelist_total_created
+                .checked_add(1)
+                .ok_or(error!(FundError::MathOverflow))?;
+            fund_account.whitelist_entries = fund_account
+                .whitelist_entries
+                .checked_add(1)
+                .ok_or(error!(FundError::MathOverflow))?;
+        }
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistBatchAdded,
+            ctx.accounts.admin.key(),
+            Pubkey::default(),
+            added,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn expire_whitelist(ctx: Context<ExpireWhitelist>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        // Permissionless crank: anyone may deactivate an entry past its expiry
+        require!(
+            whitelist_entry.is_active,
+            FundError::WhitelistEntryNotActive
+        );
+        
+        require!(
+            whitelist_expired(whitelist_entry, Clock::get()?.unix_timestamp),
+            FundError::WhitelistNotExpired
+        );
+        
+        whitelist_entry.is_active = false;
+        
+        fund_account.whitelist_active = fund_account
+            .whitelist_active
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistExpired,
+            ctx.accounts.caller.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn set_tier_limits(ctx: Context<SetTierLimits>, tier_limits: [u64; MAX_TIERS]) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        fund_account.tier_limits = tier_limits;
+        
+        Ok(())
+    }
+
+    pub fn set_whitelist_tier(ctx: Context<SetWhitelistTier>, tier: u8) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            (tier as usize) < MAX_TIERS,
+            FundError::InvalidTier
+        );
+        
+        let old_tier = std::mem::replace(&mut whitelist_entry.tier, tier);
+        
+        emit!(WhitelistTierUpdated {
+            address: whitelist_entry.address,
+            old_tier,
+            new_tier: tier,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistTierUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn distribute_pro_rata<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AllocateFundsBatch<'info>>,
+        total_amount: u64,
+    ) -> Result<()> {
+        require!(total_amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &ctx.accounts.fund_account;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            can_allocate(fund_account, &ctx.accounts.authority, ctx.accounts.operator.as_ref()),
+            FundError::UnauthorizedAllocator
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // Each recipient supplies a (whitelist_entry, to_token_account) pair
+        require!(
+            !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.len().is_multiple_of(2),
+            FundError::InvalidBatchAccounts
+        );
+        
+        require!(
+            ctx.remaining_accounts.len() / 2 <= MAX_BATCH_SIZE,
+            FundError::BatchTooLarge
+        );
+        
+        let weights = ctx
+            .remaining_accounts
+            .chunks(2)
+            .map(|pair| Ok(Account::<WhitelistEntry>::try_from(&pair[0])?.weight as u128))
+            .collect::<Result<Vec<u128>>>()?;
+        let total_weight: u128 = weights.iter().sum();
+        require!(total_weight > 0, FundError::ZeroTotalWeight);
+        
+        let mut amounts = weights
+            .iter()
+            .map(|weight| (total_amount as u128 * weight / total_weight) as u64)
+            .collect::<Vec<u64>>();
+        
+        // Integer division rounds every share down; the dust goes to the first recipient
+        let distributed = amounts
+            .iter()
+            .try_fold(0u64, |total, amount| total.checked_add(*amount))
+            .ok_or(error!(FundError::MathOverflow))?;
+        amounts[0] = amounts[0]
+            .checked_add(total_amount - distributed)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        allocate_to_pairs(ctx.accounts, ctx.remaining_accounts, &ctx.bumps, &amounts)
+    }
+
+    pub fn set_whitelist_weight(ctx: Context<SetWhitelistWeight>, weight: u16) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        whitelist_entry.weight = weight;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistWeightUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn set_whitelist_can_deposit(ctx: Context<SetWhitelistCanDeposit>, can_deposit: bool) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        whitelist_entry.can_deposit = can_deposit;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::WhitelistDepositAccessUpdated,
+            ctx.accounts.admin.key(),
+            whitelist_entry.address,
+            0,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn close_depositor(ctx: Context<CloseDepositor>) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let depositor = &ctx.accounts.depositor;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // Deposits are pooled, so a contribution that allocations have already
+        // spent can never be withdrawn and does not keep the record open
+        let withdrawable = depositor.total_deposited.min(fund_account.total_funds);
+        require!(
+            withdrawable == 0,
+            FundError::DepositorBalanceNotZero
+        );
+        
+        Ok(())
+    }
+
+    pub fn reconcile(ctx: Context<Reconcile>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // Direct transfers to the vault bypass store_funds; trust the token balance,
+        // less whatever is still earmarked for open claims
+        let old_total = fund_account.total_funds;
+        fund_account.total_funds = ctx
+            .accounts
+            .fund_token_account
+            .amount
+            .saturating_sub(fund_account.total_claimable);
+        
+        // Correct downward too, but flag it: the vault should never hold
+        // less than the books say
+        if fund_account.total_funds < old_total {
+            emit!(VaultShortfallDetected {
+                old_total,
+                new_total: fund_account.total_funds,
+                shortfall: old_total - fund_account.total_funds,
+            });
+        } else {
+            emit!(FundsReconciled {
+                old_total,
+                new_total: fund_account.total_funds,
+                surplus: fund_account.total_funds - old_total,
+            });
+        }
+        
+        Ok(())
+    }
+
+    pub fn withdraw_deposit(ctx: Context<WithdrawDeposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let depositor = &mut ctx.accounts.depositor;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(!fund_account.deposits_locked, FundError::DepositsLocked);
+        
+        require!(
+            depositor.total_deposited >= amount,
+            FundError::WithdrawalExceedsDeposit
+        );
+        
+        // Deposits are pooled, so allocations may already have spent this contribution
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.destination_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        depositor.total_deposited = depositor
+            .total_deposited
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+
+        emit!(DepositWithdrawn {
+            depositor: depositor.depositor,
+            vault: ctx.accounts.fund_token_account.key(),
+            amount,
+            remaining_deposit: depositor.total_deposited,
+            new_total: fund_account.total_funds,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_deposits_locked(ctx: Context<SetDepositsLocked>, locked: bool) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        fund_account.deposits_locked = locked;
+        
+        Ok(())
+    }
+
+    pub fn set_restricted_deposits(ctx: Context<SetRestrictedDeposits>, restricted: bool) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        fund_account.restricted_deposits = restricted;
+        
+        Ok(())
+    }
+
+    pub fn set_allocation_rate_limit(
+        ctx: Context<SetAllocationRateLimit>,
+        max_allocations_per_hour: u32,
+        window_secs: i64,
+    ) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(window_secs > 0, FundError::InvalidRateWindow);
+        
+        let old_limit = fund_account.max_allocations_per_hour;
+        fund_account.max_allocations_per_hour = max_allocations_per_hour;
+        fund_account.allocation_rate_window_secs = window_secs;
+        
+        emit!(AllocationRateLimitUpdated {
+            fund: fund_account.key(),
+            old_limit,
+            new_limit: max_allocations_per_hour,
+            window_secs,
+            actor: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_open_deposits(ctx: Context<SetOpenDeposits>, open: bool) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        let fund = fund_account.key();
+        apply_open_deposits(fund_account, fund, open, ctx.accounts.admin.key())
+    }
+
+    pub fn set_claim_grace_period(ctx: Context<SetClaimGracePeriod>, grace_secs: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(grace_secs >= 0, FundError::InvalidClaimGrace);
+        
+        fund_account.claim_grace_secs = grace_secs;
+        
+        Ok(())
+    }
+
+    pub fn create_claim(ctx: Context<CreateClaim>, amount: u64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let claim = &mut ctx.accounts.claim;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        require!(
+            !requires_approval(fund_account, amount),
+            FundError::AllocationRequiresApproval
+        );
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            whitelist_entry.target_kind == TargetKind::Owner,
+            FundError::OwnerEntryRequired
+        );
+        let total_allocated = check_recipient(fund_account, whitelist_entry, amount, timestamp)?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+        
+        // Earmark the amount; the tokens stay in the vault until claimed
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+        
+        claim.fund = fund_account.key();
+        claim.nonce = fund_account.claim_nonce;
+        claim.whitelist_entry = whitelist_entry.key();
+        claim.recipient = whitelist_entry.address;
+        claim.amount = amount;
+        claim.created_at = timestamp;
+        claim.claimed = false;
+        claim.bump = ctx.bumps.claim;
+        
+        fund_account.claim_nonce = fund_account
+            .claim_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        emit!(ClaimCreated {
+            claim: claim.key(),
+            recipient: claim.recipient,
+            amount,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CreateClaim,
+            ctx.accounts.admin.key(),
+            claim.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn claim_funds(ctx: Context<ClaimFunds>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let claim = &mut ctx.accounts.claim;
+        let amount = claim.amount;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(!claim.claimed, FundError::ClaimAlreadySettled);
+        
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.to_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        claim.claimed = true;
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            claim.recipient,
+            amount,
+            timestamp,
+        );
+        
+        emit!(FundsClaimed {
+            claim: claim.key(),
+            recipient: claim.recipient,
+            destination: ctx.accounts.to_token_account.key(),
+            amount,
+            fee,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ClaimFunds,
+            ctx.accounts.recipient.key(),
+            claim.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_claim(ctx: Context<CancelClaim>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let claim = &ctx.accounts.claim;
+        let amount = claim.amount;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(!claim.claimed, FundError::ClaimAlreadySettled);
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            timestamp.saturating_sub(claim.created_at) >= fund_account.claim_grace_secs,
+            FundError::ClaimGracePeriodActive
+        );
+        
+        // Return the earmark to the pool and release the recipient's cap
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = whitelist_entry.total_allocated.saturating_sub(amount);
+        
+        emit!(ClaimCancelled {
+            claim: claim.key(),
+            recipient: claim.recipient,
+            amount,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CancelClaim,
+            ctx.accounts.admin.key(),
+            claim.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn create_vesting(
+        ctx: Context<CreateVesting>,
+        total_amount: u64,
+        start_ts: i64,
+        end_ts: i64,
+        recipient: Pubkey,
+    ) -> Result<()> {
+        require!(total_amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let schedule = &mut ctx.accounts.schedule;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        require!(
+            !requires_approval(fund_account, total_amount),
+            FundError::AllocationRequiresApproval
+        );
+        
+        require!(end_ts > start_ts, FundError::InvalidVestingSchedule);
+        
+        require!(
+            fund_account.total_funds >= total_amount,
+            FundError::InsufficientFunds
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            whitelist_entry.target_kind == TargetKind::Owner,
+            FundError::OwnerEntryRequired
+        );
+        let total_allocated = check_recipient(fund_account, whitelist_entry, total_amount, timestamp)?;
+        record_daily_allocation(fund_account, total_amount, timestamp)?;
+        
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(total_amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_add(total_amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, total_amount, timestamp)?;
+        
+        schedule.fund = fund_account.key();
+        schedule.nonce = fund_account.vesting_nonce;
+        schedule.whitelist_entry = whitelist_entry.key();
+        schedule.recipient = recipient;
+        schedule.total_amount = total_amount;
+        schedule.claimed_amount = 0;
+        schedule.start_ts = start_ts;
+        schedule.end_ts = end_ts;
+        schedule.revoked = false;
+        schedule.bump = ctx.bumps.schedule;
+        
+        fund_account.vesting_nonce = fund_account
+            .vesting_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        emit!(VestingCreated {
+            schedule: schedule.key(),
+            recipient,
+            total_amount,
+            start_ts,
+            end_ts,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CreateVesting,
+            ctx.accounts.admin.key(),
+            recipient,
+            total_amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let schedule = &mut ctx.accounts.schedule;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            ctx.accounts.whitelist_entry.is_active,
+            FundError::RecipientNotWhitelisted
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        let amount = vested_amount(schedule, timestamp)?
+            .checked_sub(schedule.claimed_amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        require!(amount > 0, FundError::NothingVested);
+        
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.to_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        schedule.claimed_amount = schedule
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            schedule.recipient,
+            amount,
+            timestamp,
+        );
+        
+        emit!(VestedFundsClaimed {
+            schedule: schedule.key(),
+            recipient: schedule.recipient,
+            amount,
+            fee,
+            claimed_amount: schedule.claimed_amount,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ClaimVested,
+            ctx.accounts.recipient.key(),
+            schedule.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let schedule = &mut ctx.accounts.schedule;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(!schedule.revoked, FundError::VestingAlreadyRevoked);
+        
+        // What has vested stays claimable; only the remainder returns to the pool
+        let timestamp = Clock::get()?.unix_timestamp;
+        let vested = vested_amount(schedule, timestamp)?;
+        let unvested = schedule
+            .total_amount
+            .checked_sub(vested)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        
+        fund_account.total_claimable = fund_account
+            .total_claimable
+            .checked_sub(unvested)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_add(unvested)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = whitelist_entry.total_allocated.saturating_sub(unvested);
+        
+        // Freeze the schedule at what has vested so far
+        schedule.total_amount = vested;
+        schedule.end_ts = timestamp;
+        schedule.revoked = true;
+        
+        emit!(VestingRevoked {
+            schedule: schedule.key(),
+            recipient: schedule.recipient,
+            vested,
+            returned: unvested,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::RevokeVesting,
+            ctx.accounts.admin.key(),
+            schedule.recipient,
+            unvested,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn allocate_locked(ctx: Context<AllocateLocked>, amount: u64, unlock_at: i64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        require!(
+            !requires_approval(fund_account, amount),
+            FundError::AllocationRequiresApproval
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(unlock_at > timestamp, FundError::InvalidUnlockTime);
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        // The recipient is only checked here; the unlock is a permissionless crank
+        require!(
+            whitelist_entry.target_kind == TargetKind::Owner,
+            FundError::OwnerEntryRequired
+        );
+        let total_allocated = check_recipient(fund_account, whitelist_entry, amount, timestamp)?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+        
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.lock_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+        
+        let lock = &mut ctx.accounts.lock;
+        lock.fund = fund_account.key();
+        lock.whitelist_entry = whitelist_entry.key();
+        lock.recipient = whitelist_entry.address;
+        lock.vault = ctx.accounts.lock_token_account.key();
+        lock.amount = amount;
+        lock.unlock_at = unlock_at;
+        lock.payer = ctx.accounts.admin.key();
+        lock.bump = ctx.bumps.lock;
+        
+        fund_account.open_locks = fund_account
+            .open_locks
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        emit!(LockedAllocationCreated {
+            lock: lock.key(),
+            recipient: lock.recipient,
+            amount,
+            unlock_at,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::LockedAllocation,
+            ctx.accounts.admin.key(),
+            lock.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn unlock_allocation(ctx: Context<UnlockAllocation>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let lock = &ctx.accounts.lock;
+        let amount = lock.amount;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(timestamp >= lock.unlock_at, FundError::LockNotReleased);
+        
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.lock_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.recipient_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        close_pda_token_account(
+            fund_account,
+            &ctx.accounts.lock_token_account,
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.open_locks = fund_account
+            .open_locks
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            lock.recipient,
+            amount,
+            timestamp,
+        );
+        
+        emit!(LockedAllocationReleased {
+            lock: lock.key(),
+            recipient: lock.recipient,
+            amount,
+            fee,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::UnlockAllocation,
+            ctx.accounts.caller.key(),
+            lock.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_locked_allocation(ctx: Context<CancelLockedAllocation>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let lock = &ctx.accounts.lock;
+        let amount = lock.amount;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // Once unlocked the tokens belong to the recipient
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(timestamp < lock.unlock_at, FundError::LockAlreadyReleasable);
+        
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.lock_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fund_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        close_pda_token_account(
+            fund_account,
+            &ctx.accounts.lock_token_account,
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = whitelist_entry.total_allocated.saturating_sub(amount);
+        fund_account.open_locks = fund_account
+            .open_locks
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        emit!(LockedAllocationCancelled {
+            lock: lock.key(),
+            recipient: lock.recipient,
+            amount,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CancelLockedAllocation,
+            ctx.accounts.admin.key(),
+            lock.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    // Unlike a lock, several escrows can be pending for the same recipient.
+    // The admin can veto one until release_after; after that anyone can
+    // release it.
+    pub fn allocate_escrowed(ctx: Context<AllocateEscrowed>, amount: u64, release_after: i64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        require!(
+            !requires_approval(fund_account, amount),
+            FundError::AllocationRequiresApproval
+        );
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(release_after > timestamp, FundError::InvalidReleaseTime);
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        require!(
+            whitelist_entry.target_kind == TargetKind::Owner,
+            FundError::OwnerEntryRequired
+        );
+        let total_allocated = check_recipient(fund_account, whitelist_entry, amount, timestamp)?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+        
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.escrow_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+        
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.fund = fund_account.key();
+        escrow.nonce = fund_account.escrow_nonce;
+        escrow.whitelist_entry = whitelist_entry.key();
+        escrow.recipient = whitelist_entry.address;
+        escrow.vault = ctx.accounts.escrow_token_account.key();
+        escrow.amount = amount;
+        escrow.release_after = release_after;
+        escrow.payer = ctx.accounts.admin.key();
+        escrow.bump = ctx.bumps.escrow;
+        
+        fund_account.escrow_nonce = fund_account
+            .escrow_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.open_escrows = fund_account
+            .open_escrows
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        emit!(EscrowCreated {
+            escrow: escrow.key(),
+            recipient: escrow.recipient,
+            amount,
+            release_after,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::EscrowedAllocation,
+            ctx.accounts.admin.key(),
+            escrow.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let escrow = &ctx.accounts.escrow;
+        let amount = escrow.amount;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(timestamp >= escrow.release_after, FundError::EscrowNotReleased);
+        
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.recipient_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        close_pda_token_account(
+            fund_account,
+            &ctx.accounts.escrow_token_account,
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.open_escrows = fund_account
+            .open_escrows
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            escrow.recipient,
+            amount,
+            timestamp,
+        );
+        
+        emit!(EscrowReleased {
+            escrow: escrow.key(),
+            recipient: escrow.recipient,
+            amount,
+            fee,
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::ReleaseEscrow,
+            ctx.accounts.caller.key(),
+            escrow.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_escrow(ctx: Context<CancelEscrow>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let escrow = &ctx.accounts.escrow;
+        let amount = escrow.amount;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // The veto window closes at release_after
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(timestamp < escrow.release_after, FundError::EscrowAlreadyReleasable);
+        
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fund_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        close_pda_token_account(
+            fund_account,
+            &ctx.accounts.escrow_token_account,
+            ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = whitelist_entry.total_allocated.saturating_sub(amount);
+        fund_account.open_escrows = fund_account
+            .open_escrows
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        emit!(EscrowCancelled {
+            escrow: escrow.key(),
+            recipient: escrow.recipient,
+            amount,
+            actor: ctx.accounts.admin.key(),
+            timestamp,
+        });
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CancelEscrow,
+            ctx.accounts.admin.key(),
+            escrow.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn create_recurring(
+        ctx: Context<CreateRecurring>,
+        amount: u64,
+        interval_secs: i64,
+        first_run_at: i64,
+        runs: u32,
+    ) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &mut ctx.accounts.fund_account;
+        let schedule = &mut ctx.accounts.schedule;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        require!(
+            !requires_approval(fund_account, amount),
+            FundError::AllocationRequiresApproval
+        );
+        
+        require!(
+            interval_secs > 0 && runs > 0,
+            FundError::InvalidRecurringSchedule
+        );
+        
+        // Whitelist and balance checks run at execution time, not here
+        schedule.fund = fund_account.key();
+        schedule.nonce = fund_account.recurring_nonce;
+        schedule.whitelist_entry = ctx.accounts.whitelist_entry.key();
+        schedule.recipient = ctx.accounts.whitelist_entry.address;
+        schedule.amount = amount;
+        schedule.interval_secs = interval_secs;
+        schedule.next_run_at = first_run_at;
+        schedule.remaining_runs = runs;
+        schedule.payer = ctx.accounts.admin.key();
+        schedule.bump = ctx.bumps.schedule;
+        
+        fund_account.recurring_nonce = fund_account
+            .recurring_nonce
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        fund_account.open_recurring = fund_account
+            .open_recurring
+            .checked_add(1)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CreateRecurring,
+            ctx.accounts.admin.key(),
+            schedule.recipient,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn execute_recurring(ctx: Context<ExecuteRecurring>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        let whitelist_entry = &mut ctx.accounts.whitelist_entry;
+        let schedule = &mut ctx.accounts.schedule;
+        let amount = schedule.amount;
+        
+        require!(!fund_account.paused, FundError::FundPaused);
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(timestamp >= schedule.next_run_at, FundError::RecurringNotDue);
+        
+        require!(
+            fund_account.total_funds >= amount,
+            FundError::InsufficientFunds
+        );
+        
+        let total_allocated = check_allocation(
+            fund_account,
+            whitelist_entry,
+            &ctx.accounts.to_token_account,
+            &ctx.accounts.fund_token_account,
+            amount,
+            timestamp,
+        )?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+
+        let fee = transfer_with_fee(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.fee_token_account.as_ref(),
+            ctx.accounts.to_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(whitelist_entry, amount, timestamp)?;
+
+        record_allocation(
+            &mut ctx.accounts.allocation_log,
+            whitelist_entry.address,
+            amount,
+            timestamp,
+        );
+
+        emit!(FundsAllocated {
+            recipient: whitelist_entry.address,
+            vault: ctx.accounts.fund_token_account.key(),
+            amount,
+            fee,
+            new_total: fund_account.total_funds,
+            total_allocated: fund_account.total_allocated,
+            memo: String::new(),
+            reason_code: None,
+            valid_until: 0,
+            allocation_count: whitelist_entry.allocation_count,
+            timestamp,
+        });
+        
+        // Advance from the scheduled time rather than now so a late crank
+        // does not shift every later run
+        schedule.next_run_at = schedule
+            .next_run_at
+            .checked_add(schedule.interval_secs)
+            .ok_or(error!(FundError::MathOverflow))?;
+        schedule.remaining_runs = schedule
+            .remaining_runs
+            .checked_sub(1)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        
+        if schedule.remaining_runs == 0 {
+            fund_account.open_recurring = fund_account
+                .open_recurring
+                .checked_sub(1)
+                .ok_or(error!(FundError::CounterUnderflow))?;
+            schedule.close(ctx.accounts.payer.to_account_info())?;
+        }
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::RecurringAllocation,
+            ctx.accounts.caller.key(),
+            whitelist_entry.address,
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn cancel_recurring(ctx: Context<CancelRecurring>) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        fund_account.open_recurring = fund_account
+            .open_recurring
+            .checked_sub(1)
+            .ok_or(error!(FundError::CounterUnderflow))?;
+        
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::CancelRecurring,
+            ctx.accounts.admin.key(),
+            ctx.accounts.schedule.recipient,
+            ctx.accounts.schedule.amount,
+        )?;
+        
+        Ok(())
+    }
+
+    pub fn recover_token(ctx: Context<RecoverToken>, amount: u64) -> Result<()> {
+        require!(amount > 0, FundError::ZeroAmount);
+        
+        let fund_account = &ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // The PDA signs for any account it owns, so the mint constraint on
+        // RecoverToken is what keeps the real vault out of reach
+        transfer_from_vault(
+            fund_account,
+            &ctx.accounts.source_token_account,
+            &ctx.accounts.mint,
+            ctx.accounts.destination_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        
+        emit!(TokensRecovered {
+            mint: ctx.accounts.mint.key(),
+            source: ctx.accounts.source_token_account.key(),
+            destination: ctx.accounts.destination_token_account.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Lamports sent straight to the fund PDA are not deposits; this returns
+    // whatever sits above the rent-exempt minimum for the account's size
+    pub fn sweep_lamports(ctx: Context<SweepLamports>) -> Result<()> {
+        require!(
+            is_admin(&ctx.accounts.fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            ctx.accounts.fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        let fund_info = ctx.accounts.fund_account.to_account_info();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(fund_info.data_len());
+        let amount = fund_info.lamports().saturating_sub(rent_exempt_minimum);
+        require!(amount > 0, FundError::NothingToSweep);
+        
+        // The fund PDA is program-owned, so lamports move directly
+        **fund_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.destination.try_borrow_mut_lamports()? += amount;
+        
+        emit!(LamportsSwept {
+            fund: fund_info.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_fund(ctx: Context<CloseFund>) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // Open claims and vesting schedules still have tokens earmarked in the vault
+        require!(
+            fund_account.total_funds == 0 && fund_account.total_claimable == 0,
+            FundError::FundNotEmpty
+        );
+        
+        // Locked and escrowed tokens sit in their own vaults and recurring
+        // schedules still reference the fund; all are stranded once the fund
+        // account is gone
+        require!(
+            fund_account.open_locks == 0
+                && fund_account.open_escrows == 0
+                && fund_account.open_recurring == 0,
+            FundError::OpenAllocationsRemain
+        );
+        
+        // Inactive entries count too, since close_whitelist needs the fund account
+        require!(
+            fund_account.whitelist_entries == 0,
+            FundError::WhitelistNotEmpty
+        );
+        
+        // Anything left is dust or stray transfers outside store_funds
+        let remainder = ctx.accounts.fund_token_account.amount;
+        if remainder > 0 {
+            let destination = ctx
+                .accounts
+                .destination_token_account
+                .as_ref()
+                .ok_or(error!(FundError::SweepDestinationRequired))?;
+            transfer_from_vault(
+                fund_account,
+                &ctx.accounts.fund_token_account,
+                &ctx.accounts.mint,
+                destination.to_account_info(),
+                &ctx.accounts.token_program,
+                remainder,
+            )?;
+        }
+        
+        close_pda_token_account(
+            fund_account,
+            &ctx.accounts.fund_token_account,
+            ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+        
+        emit!(FundClosed {
+            fund: fund_account.key(),
+            mint: fund_account.mint,
+            swept: remainder,
+            admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_audit_config(ctx: Context<SetAuditConfig>, enabled: bool, retention_secs: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        // Recorded under the old config, so turning auditing off is itself audited
+        record_audit(
+            fund_account,
+            ctx.accounts.audit_record.as_mut(),
+            ctx.bumps.audit_record,
+            AuditAction::SetAuditConfig,
+            ctx.accounts.admin.key(),
+            Pubkey::default(),
+            0,
+        )?;
+        
+        apply_audit_config(fund_account, enabled, retention_secs)
+    }
+
+    pub fn prune_audit_records<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PruneAuditRecords<'info>>,
+    ) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        // Each record is followed by the account that paid its rent
+        require!(
+            !ctx.remaining_accounts.is_empty() && ctx.remaining_accounts.len().is_multiple_of(2),
+            FundError::InvalidBatchAccounts
+        );
+        
+        require!(
+            ctx.remaining_accounts.len() / 2 <= MAX_BATCH_SIZE,
+            FundError::BatchTooLarge
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let mut pruned = 0u32;
+        
+        for pair in ctx.remaining_accounts.chunks(2) {
+            let audit_record = Account::<AuditRecord>::try_from(&pair[0])?;
+            
+            require!(
+                audit_record.fund == fund_account.key() && audit_record.payer == pair[1].key(),
+                FundError::AuditRecordMismatch
+            );
+            
+            require!(
+                now.saturating_sub(audit_record.timestamp) >= fund_account.audit_retention_secs,
+                FundError::AuditRecordTooRecent
+            );
+            
+            audit_record.close(pair[1].clone())?;
+            pruned += 1;
+        }
+        
+        emit!(AuditRecordsPruned {
+            fund: fund_account.key(),
+            pruned,
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    pub fn create_delegation(
+        ctx: Context<CreateDelegation>,
+        delegate: Pubkey,
+        expires_at: i64,
+        per_allocation_cap: u64,
+    ) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        let delegation = &mut ctx.accounts.delegation;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            fund_account.admin_threshold <= 1,
+            FundError::ThresholdApprovalRequired
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            expires_at > now,
+            FundError::InvalidDelegationExpiry
+        );
+        
+        delegation.fund = fund_account.key();
+        delegation.delegate = delegate;
+        delegation.expires_at = expires_at;
+        delegation.per_allocation_cap = per_allocation_cap;
+        delegation.created_by = ctx.accounts.admin.key();
+        delegation.payer = ctx.accounts.admin.key();
+        delegation.bump = ctx.bumps.delegation;
+        
+        emit!(DelegationCreated {
+            fund: fund_account.key(),
+            delegate,
+            expires_at,
+            per_allocation_cap,
+            actor: ctx.accounts.admin.key(),
+            timestamp: now,
+        });
+        
+        Ok(())
+    }
+
+    // Closing the account revokes the delegation, expired or not
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        let fund_account = &ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        emit!(DelegationRevoked {
+            fund: fund_account.key(),
+            delegate: ctx.accounts.delegation.delegate,
+            actor: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Raising the delay applies at once. Lowering it only applies once the
+    // current delay has run, so a stolen key cannot shortcut the timelock.
+    pub fn set_admin_change_delay(ctx: Context<SetAdminChangeDelay>, delay_secs: i64) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            delay_secs >= 0,
+            FundError::InvalidAdminChangeDelay
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let current_delay = settle_admin_change_delay(fund_account, now);
+        let effective_at = if delay_secs >= current_delay {
+            fund_account.admin_change_delay_secs = delay_secs;
+            fund_account.pending_admin_change_delay = 0;
+            fund_account.admin_delay_change_at = 0;
+            now
+        } else {
+            let effective_at = now
+                .checked_add(current_delay)
+                .ok_or(error!(FundError::MathOverflow))?;
+            fund_account.pending_admin_change_delay = delay_secs;
+            fund_account.admin_delay_change_at = effective_at;
+            effective_at
+        };
+        
+        emit!(AdminChangeDelayUpdated {
+            fund: fund_account.key(),
+            old_delay_secs: current_delay,
+            new_delay_secs: delay_secs,
+            effective_at,
+            actor: ctx.accounts.admin.key(),
+        });
+        
+        Ok(())
+    }
+
+    // Rebuilds the whitelist counters from the fund's entries, for accounts
+    // whose counters predate whitelist_total_created or have drifted. Large
+    // whitelists are recounted in chunks: `reset` on the first chunk only.
+    // Closed entries are gone, so total_created becomes a lower bound.
+    pub fn recount_whitelist<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecountWhitelist<'info>>,
+        reset: bool,
+    ) -> Result<()> {
+        let fund_account = &mut ctx.accounts.fund_account;
+        
+        require!(
+            is_admin(fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        require!(
+            ctx.remaining_accounts.len() <= MAX_BATCH_SIZE,
+            FundError::BatchTooLarge
+        );
+        
+        for (i, account) in ctx.remaining_accounts.iter().enumerate() {
+            require!(
+                !ctx.remaining_accounts[..i].iter().any(|other| other.key() == account.key()),
+                FundError::DuplicateBatchAddress
+            );
+        }
+        
+        if reset {
+            fund_account.whitelist_active = 0;
+            fund_account.whitelist_total_created = 0;
+            fund_account.whitelist_entries = 0;
+        }
+        
+        for account in ctx.remaining_accounts.iter() {
+            let whitelist_entry = Account::<WhitelistEntry>::try_from(account)?;
+            require!(
+                whitelist_entry.fund_id == fund_account.fund_id,
+                FundError::WhitelistFundMismatch
+            );
+            
+            fund_account.whitelist_total_created = fund_account
+                .whitelist_total_created
+                .checked_add(1)
+                .ok_or(error!(FundError::MathOverflow))?;
+            fund_account.whitelist_entries = fund_account
+                .whitelist_entries
+                .checked_add(1)
+                .ok_or(error!(FundError::MathOverflow))?;
+            if whitelist_entry.is_active {
+                fund_account.whitelist_active = fund_account
+                    .whitelist_active
+                    .checked_add(1)
+                    .ok_or(error!(FundError::MathOverflow))?;
+            }
+        }
+        
+        emit!(WhitelistRecounted {
+            fund: fund_account.key(),
+            whitelist_active: fund_account.whitelist_active,
+            whitelist_total_created: fund_account.whitelist_total_created,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_allocation_nonce(ctx: Context<CloseAllocationNonce>) -> Result<()> {
+        require!(
+            is_admin(&ctx.accounts.fund_account, &ctx.accounts.admin.key()),
+            FundError::UnauthorizedAdmin
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        let eligible_at = ctx
+            .accounts
+            .nonce_record
+            .created_at
+            .checked_add(ALLOCATION_NONCE_RETENTION_SECS)
+            .ok_or(error!(FundError::MathOverflow))?;
+        require!(now >= eligible_at, FundError::AllocationNonceTooRecent);
+        
+        Ok(())
+    }
+}
+
+pub const MAX_BATCH_SIZE: usize = 10;
+pub const MAX_FEE_BPS: u16 = 10_000;
+pub const DAILY_WINDOW_SECS: i64 = 86_400;
+// The primary admin plus up to four co-admins
+pub const MAX_ADMINS: usize = 5;
+pub const ALLOCATION_LOG_SIZE: usize = 32;
+pub const MAX_TIERS: usize = 4;
+pub const MAX_MEMO_LEN: usize = 128;
+pub const DEFAULT_ALLOCATION_RATE_WINDOW_SECS: i64 = 3_600;
+// How long a nonce must be kept before its rent can be reclaimed. A retry
+// arriving after the record is closed would be accepted again.
+pub const ALLOCATION_NONCE_RETENTION_SECS: i64 = 7 * DAILY_WINDOW_SECS;
+
+fn allocate(
+    accounts: &mut AllocateFunds,
+    bumps: &AllocateFundsBumps,
+    amount: u64,
+    memo: String,
+    reason_code: Option<u16>,
+    valid_until: i64,
+    min_vault_balance_after: u64,
+) -> Result<u64> {
+    require!(amount > 0, FundError::ZeroAmount);
+    require!(memo.len() <= MAX_MEMO_LEN, FundError::MemoTooLong);
+    
+    let fund_account = &mut accounts.fund_account;
+    let whitelist_entry = &mut accounts.whitelist_entry;
+    
+    require!(!fund_account.paused, FundError::FundPaused);
+    
+    let timestamp = Clock::get()?.unix_timestamp;
+    require!(
+        valid_until == 0 || timestamp <= valid_until,
+        FundError::AllocationExpired
+    );
+    require!(
+        can_allocate(fund_account, &accounts.authority, accounts.operator.as_ref())
+            || delegation_covers(accounts.delegation.as_ref(), amount, timestamp),
+        FundError::UnauthorizedAllocator
+    );
+    record_signer_allocations(
+        fund_account,
+        accounts.signer_rate_limit.as_mut(),
+        bumps.signer_rate_limit,
+        accounts.authority.key(),
+        1,
+        timestamp,
+    )?;
+    
+    require!(
+        fund_account.admin_threshold <= 1,
+        FundError::ThresholdApprovalRequired
+    );
+    
+    require!(
+        !requires_approval(fund_account, amount),
+        FundError::AllocationRequiresApproval
+    );
+    
+    require!(
+        fund_account.total_funds >= amount,
+        FundError::InsufficientFunds
+    );
+    
+    let total_allocated = check_allocation(
+        fund_account,
+        whitelist_entry,
+        &accounts.to_token_account,
+        &accounts.fund_token_account,
+        amount,
+        timestamp,
+    )?;
+    record_daily_allocation(fund_account, amount, timestamp)?;
+
+    let fee = transfer_with_fee(
+        fund_account,
+        &accounts.fund_token_account,
+        &accounts.mint,
+        accounts.fee_token_account.as_ref(),
+        accounts.to_token_account.to_account_info(),
+        &accounts.token_program,
+        amount,
+    )?;
+    
+    if min_vault_balance_after > 0 {
+        accounts.fund_token_account.reload()?;
+        require!(
+            accounts.fund_token_account.amount >= min_vault_balance_after,
+            FundError::VaultBalanceBelowMinimum
+        );
+    }
+
+    fund_account.total_funds = fund_account
+        .total_funds
+        .checked_sub(amount)
+        .ok_or(error!(FundError::MathUnderflow))?;
+    fund_account.total_allocated = fund_account
+        .total_allocated
+        .checked_add(amount)
+        .ok_or(error!(FundError::MathOverflow))?;
+    whitelist_entry.total_allocated = total_allocated;
+    record_entry_allocation(whitelist_entry, amount, timestamp)?;
+
+    record_allocation(
+        &mut accounts.allocation_log,
+        whitelist_entry.address,
+        amount,
+        timestamp,
+    );
+    record_audit(
+        fund_account,
+        accounts.audit_record.as_mut(),
+        bumps.audit_record,
+        AuditAction::Allocation,
+        accounts.authority.key(),
+        whitelist_entry.address,
+        amount,
+    )?;
+
+    emit!(FundsAllocated {
+        recipient: whitelist_entry.address,
+        vault: accounts.fund_token_account.key(),
+        amount,
+        fee,
+        new_total: fund_account.total_funds,
+        total_allocated: fund_account.total_allocated,
+        memo: memo.clone(),
+        reason_code,
+        valid_until,
+        allocation_count: whitelist_entry.allocation_count,
+        timestamp,
+    });
+    
+    // Puts the memo on the transaction itself so explorers show it
+    if let Some(memo_program) = &accounts.memo_program {
+        if !memo.is_empty() {
+            memo::build_memo(
+                CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+                memo.as_bytes(),
+            )?;
+        }
+    }
+
+    // Anchor writes the returned value as return data (little-endian u64)
+    // so a calling program can read the new total with get_return_data
+    Ok(fund_account.total_funds)
+}
+
+// Recipient checks shared by every allocation path. Returns the entry's
+// new total_allocated so the caller can commit it after the transfer.
+fn check_allocation(
+    fund_account: &FundAccount,
+    whitelist_entry: &WhitelistEntry,
+    to_token_account: &InterfaceAccount<TokenAccount>,
+    fund_token_account: &TokenAccount,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    let total_allocated = check_recipient(fund_account, whitelist_entry, amount, now)?;
+    
+    require!(
+        whitelist_entry.address == whitelist_entry.target_kind.bound_key(to_token_account),
+        FundError::WhitelistAddressMismatch
+    );
+    
+    // Also enforced by the Accounts constraints; kept for remaining_accounts
+    require!(
+        fund_token_account.mint == to_token_account.mint,
+        FundError::MintMismatch
+    );
+    
+    // Paying into an account the fund itself owns would lower total_funds
+    // while the tokens never leave, so the books would drift from the vault
+    require!(
+        to_token_account.owner != fund_token_account.owner,
+        FundError::SelfAllocationForbidden
+    );
+    
+    Ok(total_allocated)
+}
+
+// The part of check_allocation that does not need the recipient's token
+// account, so claims can be earmarked before the recipient has one
+fn check_recipient(
+    fund_account: &FundAccount,
+    whitelist_entry: &WhitelistEntry,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    // Entries loaded from remaining_accounts skip the seeds check, so make
+    // sure they were whitelisted in this fund
+    require!(
+        whitelist_entry.fund_id == fund_account.fund_id,
+        FundError::WhitelistFundMismatch
+    );
+    
+    // A min_allocation of 0 allows any non-zero amount
+    require!(
+        fund_account.min_allocation == 0 || amount >= fund_account.min_allocation,
+        FundError::BelowMinAllocation
+    );
+    
+    require!(
+        whitelist_entry.is_active,
+        FundError::RecipientNotWhitelisted
+    );
+    
+    require!(
+        !whitelist_entry.suspended,
+        FundError::WhitelistSuspended
+    );
+    
+    require!(
+        !whitelist_expired(whitelist_entry, now),
+        FundError::WhitelistExpired
+    );
+    
+    require!(
+        now >= whitelist_entry.unlock_at,
+        FundError::AllocationLocked
+    );
+    
+    // A cooldown of 0 disables the check; entries never allocated to are exempt
+    let cooldown_secs = fund_account.allocation_cooldown_secs;
+    if cooldown_secs > 0 && whitelist_entry.last_allocated_at > 0 {
+        require!(
+            now.saturating_sub(whitelist_entry.last_allocated_at) >= cooldown_secs,
+            FundError::AllocationCooldownActive
+        );
+    }
+    
+    // Per-allocation limit for the recipient's tier; 0 means unlimited
+    let tier_limit = fund_account
+        .tier_limits
+        .get(whitelist_entry.tier as usize)
+        .ok_or(error!(FundError::InvalidTier))?;
+    require!(
+        *tier_limit == 0 || amount <= *tier_limit,
+        FundError::TierLimitExceeded
+    );
+    
+    // A period_limit of 0 disables the per-period budget
+    if whitelist_entry.period_limit > 0 {
+        let spent = spent_in_current_period(whitelist_entry, now)
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        require!(
+            spent <= whitelist_entry.period_limit,
+            FundError::PeriodBudgetExceeded
+        );
+    }
+    
+    // A max_allocation of 0 means the recipient is uncapped
+    let total_allocated = whitelist_entry
+        .total_allocated
+        .checked_add(amount)
+        .ok_or(error!(FundError::MathOverflow))?;
+    require!(
+        whitelist_entry.max_allocation == 0 || total_allocated <= whitelist_entry.max_allocation,
+        FundError::AllocationCapExceeded
+    );
+    
+    Ok(total_allocated)
+}
+
+fn transfer_from_vault<'info>(
+    fund_account: &Account<'info, FundAccount>,
+    fund_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[
+        b"fund_account".as_ref(),
+        fund_account.fund_id.as_ref(),
+        &[fund_account.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: fund_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to,
+                authority: fund_account.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+// Closes an emptied token account owned by the fund PDA
+fn close_pda_token_account<'info>(
+    fund_account: &Account<'info, FundAccount>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    destination: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let seeds = &[
+        b"fund_account".as_ref(),
+        fund_account.fund_id.as_ref(),
+        &[fund_account.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: token_account.to_account_info(),
+            destination,
+            authority: fund_account.to_account_info(),
+        },
+        signer,
+    ))
+}
+
+// The primary admin occupies approval slot 0 and co-admins follow in order
+fn admin_index(fund_account: &FundAccount, key: &Pubkey) -> Option<u8> {
+    if *key == fund_account.admin {
+        return Some(0);
+    }
+    fund_account
+        .co_admins
+        .iter()
+        .position(|co_admin| co_admin == key)
+        .map(|position| position as u8 + 1)
+}
+
+fn is_admin(fund_account: &FundAccount, key: &Pubkey) -> bool {
+    admin_index(fund_account, key).is_some()
+}
+
+fn check_admin_approvals(fund_account: &FundAccount, operation: &AdminOperation) -> Result<()> {
+    // Approval slots shift when the admin set changes, so older bitmaps are void
+    require!(
+        operation.admin_set_version == fund_account.admin_set_version,
+        FundError::StaleAdminOperation
+    );
+    
+    require!(
+        operation.approvals.count_ones() >= fund_account.admin_threshold as u32,
+        FundError::InsufficientApprovals
+    );
+    
+    Ok(())
+}
+
+// Changes to the admin set shared by the single-signer instructions and
+// execute_admin_operation
+fn apply_admin_action(fund_account: &mut FundAccount, action: &AdminAction) -> Result<()> {
+    match *action {
+        AdminAction::AddAdmin { admin } => {
+            require!(
+                !is_admin(fund_account, &admin),
+                FundError::AdminAlreadyExists
+            );
+            require!(
+                fund_account.co_admins.len() + 1 < MAX_ADMINS,
+                FundError::TooManyAdmins
+            );
+            fund_account.co_admins.push(admin);
+        }
+        AdminAction::RemoveAdmin { admin } => {
+            // The primary admin is rotated through propose_admin/accept_admin instead
+            let position = fund_account
+                .co_admins
+                .iter()
+                .position(|co_admin| *co_admin == admin)
+                .ok_or(FundError::AdminNotFound)?;
+            fund_account.co_admins.remove(position);
+            require!(
+                fund_account.admin_threshold as usize <= fund_account.co_admins.len() + 1,
+                FundError::InvalidAdminThreshold
+            );
+        }
+        AdminAction::SetThreshold { threshold } => {
+            require!(
+                threshold >= 1 && threshold as usize <= fund_account.co_admins.len() + 1,
+                FundError::InvalidAdminThreshold
+            );
+            fund_account.admin_threshold = threshold;
+        }
+        _ => return err!(FundError::InvalidAdminAction),
+    }
+    
+    fund_account.admin_set_version = fund_account
+        .admin_set_version
+        .checked_add(1)
+        .ok_or(error!(FundError::MathOverflow))?;
+    
+    Ok(())
+}
+
+// Fund settings that a multi-admin fund changes through execute_admin_operation
+fn apply_paused(fund_account: &mut FundAccount, paused: bool, actor: Pubkey) -> Result<()> {
+    fund_account.paused = paused;
+    
+    emit!(FundPausedEvent {
+        paused,
+        actor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+fn apply_fee(fund_account: &mut FundAccount, fee_bps: u16, fee_destination: Pubkey) -> Result<()> {
+    require!(
+        fee_bps <= MAX_FEE_BPS,
+        FundError::InvalidFeeBps
+    );
+    
+    fund_account.fee_bps = fee_bps;
+    fund_account.fee_destination = fee_destination;
+    
+    Ok(())
+}
+
+fn apply_daily_limit(fund_account: &mut FundAccount, daily_limit: u64, actor: Pubkey) -> Result<()> {
+    let old_limit = fund_account.daily_limit;
+    fund_account.daily_limit = daily_limit;
+    
+    emit!(DailyLimitUpdated {
+        old_limit,
+        new_limit: daily_limit,
+        actor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+fn apply_open_deposits(fund_account: &mut FundAccount, fund: Pubkey, open: bool, actor: Pubkey) -> Result<()> {
+    fund_account.open_deposits = open;
+    
+    emit!(OpenDepositsUpdated {
+        fund,
+        open_deposits: open,
+        actor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+fn apply_allocation_cooldown(fund_account: &mut FundAccount, cooldown_secs: i64) -> Result<()> {
+    require!(
+        cooldown_secs >= 0,
+        FundError::InvalidCooldown
+    );
+    
+    fund_account.allocation_cooldown_secs = cooldown_secs;
+    
+    Ok(())
+}
+
+fn apply_allocation_cap(whitelist_entry: &mut WhitelistEntry, max_allocation: u64) {
+    // 0 lifts the cap; lowering it below total_allocated simply blocks further allocations
+    whitelist_entry.max_allocation = max_allocation;
+}
+
+fn apply_period_budget(whitelist_entry: &mut WhitelistEntry, period_limit: u64, period_secs: i64) -> Result<()> {
+    // A limit needs a period to apply to; (0, 0) clears the budget
+    require!(
+        period_secs >= 0 && (period_limit == 0 || period_secs > 0),
+        FundError::InvalidBudgetPeriod
+    );
+    
+    // The current period and its spending carry over; only the bounds change
+    whitelist_entry.period_limit = period_limit;
+    whitelist_entry.period_secs = period_secs;
+    
+    Ok(())
+}
+
+fn apply_audit_config(fund_account: &mut FundAccount, enabled: bool, retention_secs: i64) -> Result<()> {
+    require!(
+        retention_secs >= 0,
+        FundError::InvalidRetentionPeriod
+    );
+    
+    fund_account.audit_enabled = enabled;
+    fund_account.audit_retention_secs = retention_secs;
+    
+    Ok(())
+}
+
+// Per-entry actions name their entry, and the optional whitelist_entry
+// account carries no seeds check, so it must be that entry of this fund
+fn admin_action_entry<'a, 'info>(
+    fund_account: &FundAccount,
+    whitelist_entry: Option<&'a mut Account<'info, WhitelistEntry>>,
+    address: Pubkey,
+) -> Result<&'a mut Account<'info, WhitelistEntry>> {
+    let whitelist_entry = whitelist_entry.ok_or(error!(FundError::WhitelistEntryRequired))?;
+    
+    require!(
+        whitelist_entry.fund_id == fund_account.fund_id,
+        FundError::WhitelistFundMismatch
+    );
+    require!(
+        whitelist_entry.address == address,
+        FundError::WhitelistAddressMismatch
+    );
+    
+    Ok(whitelist_entry)
+}
+
+// Allocations may be signed by the admin or by one of the fund's active
+// operators. The operator PDA is derived from the signer, so holding it
+// is proof the signer was registered.
+fn can_allocate(
+    fund_account: &FundAccount,
+    authority: &Signer,
+    operator: Option<&Account<Operator>>,
+) -> bool {
+    is_admin(fund_account, &authority.key()) || operator.is_some_and(|operator| operator.is_active)
+}
+
+// A delegate may allocate until expires_at, and no more than
+// per_allocation_cap at a time unless the cap is 0
+fn delegation_covers(delegation: Option<&Account<DelegatedAuthority>>, amount: u64, now: i64) -> bool {
+    delegation.is_some_and(|delegation| {
+        now < delegation.expires_at
+            && (delegation.per_allocation_cap == 0 || amount <= delegation.per_allocation_cap)
+    })
+}
+
+// An expires_at of 0 means the entry never expires. The entry is already
+// expired at the expires_at second itself.
+fn whitelist_expired(whitelist_entry: &WhitelistEntry, now: i64) -> bool {
+    whitelist_entry.expires_at != 0 && now >= whitelist_entry.expires_at
+}
+
+// Allocations at or above the approval threshold must go through
+// propose_allocation/approve_allocation. A threshold of 0 disables this.
+fn requires_approval(fund_account: &FundAccount, amount: u64) -> bool {
+    fund_account.approval_threshold > 0 && amount >= fund_account.approval_threshold
+}
+
+// A TTL of 0 means proposals never expire
+fn proposal_expired(fund_account: &FundAccount, proposal: &AllocationProposal, now: i64) -> bool {
+    fund_account.proposal_ttl_secs > 0
+        && now.saturating_sub(proposal.created_at) > fund_account.proposal_ttl_secs
+}
+
+// Linear release between start_ts and end_ts. Rounds down, so the result
+// never exceeds total_amount.
+fn vested_amount(schedule: &VestingSchedule, now: i64) -> Result<u64> {
+    if now >= schedule.end_ts {
+        return Ok(schedule.total_amount);
+    }
+    if now <= schedule.start_ts {
+        return Ok(0);
+    }
+    
+    let elapsed = (now - schedule.start_ts) as u128;
+    let duration = (schedule.end_ts - schedule.start_ts) as u128;
+    let vested = (schedule.total_amount as u128)
+        .checked_mul(elapsed)
+        .ok_or(error!(FundError::MathOverflow))?
+        / duration;
+    u64::try_from(vested).map_err(|_| error!(FundError::MathOverflow))
+}
+
+// Overwrites the oldest slot once the log has wrapped around
+fn record_allocation(allocation_log: &mut AllocationLog, recipient: Pubkey, amount: u64, timestamp: i64) {
+    let head = allocation_log.head as usize;
+    allocation_log.entries[head] = AllocationRecord {
+        recipient,
+        amount,
+        timestamp,
+    };
+    allocation_log.head = ((head + 1) % ALLOCATION_LOG_SIZE) as u8;
+}
+
+// Applies a scheduled decrease of the admin change delay once it is due and
+// returns the delay in force at `now`
+fn settle_admin_change_delay(fund_account: &mut FundAccount, now: i64) -> i64 {
+    if fund_account.admin_delay_change_at != 0 && now >= fund_account.admin_delay_change_at {
+        fund_account.admin_change_delay_secs = fund_account.pending_admin_change_delay;
+        fund_account.pending_admin_change_delay = 0;
+        fund_account.admin_delay_change_at = 0;
+    }
+    fund_account.admin_change_delay_secs
+}
+
+// Records `new_admin` as pending; accept_admin finalizes it once the delay
+// has passed. With a delay of 0 it can be accepted straight away.
+fn schedule_admin_change(fund_account: &mut Account<FundAccount>, new_admin: Pubkey, actor: Pubkey) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let delay = settle_admin_change_delay(fund_account, now);
+    let effective_at = now
+        .checked_add(delay)
+        .ok_or(error!(FundError::MathOverflow))?;
+    
+    fund_account.pending_admin = Some(new_admin);
+    fund_account.admin_change_effective_at = effective_at;
+    
+    emit!(AdminChangeScheduled {
+        fund: fund_account.key(),
+        current_admin: fund_account.admin,
+        new_admin,
+        effective_at,
+        actor,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+// Writes the supplied AuditRecord and advances action_sequence. The record
+// may only be omitted while auditing is disabled.
+fn record_audit(
+    fund_account: &mut Account<FundAccount>,
+    audit_record: Option<&mut Account<AuditRecord>>,
+    bump: Option<u8>,
+    action: AuditAction,
+    actor: Pubkey,
+    target: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    let Some(audit_record) = audit_record else {
+        require!(
+            !fund_account.audit_enabled,
+            FundError::AuditRecordRequired
+        );
+        return Ok(());
+    };
+    
+    audit_record.fund = fund_account.key();
+    audit_record.sequence = fund_account.action_sequence;
+    audit_record.action = action;
+    audit_record.actor = actor;
+    audit_record.target = target;
+    audit_record.amount = amount;
+    audit_record.timestamp = Clock::get()?.unix_timestamp;
+    audit_record.payer = actor;
+    audit_record.bump = bump.ok_or(error!(FundError::AuditRecordRequired))?;
+    
+    fund_account.action_sequence = fund_account
+        .action_sequence
+        .checked_add(1)
+        .ok_or(error!(FundError::MathOverflow))?;
+    Ok(())
+}
+
+// A fresh, active entry with no limits, shared by the add_whitelist variants
+fn new_whitelist_entry(
+    address: Pubkey,
+    target_kind: TargetKind,
+    label: String,
+    fund_id: [u8; 16],
+    added_by: Pubkey,
+    added_at: i64,
+) -> WhitelistEntry {
+    WhitelistEntry {
+        address,
+        label,
+        is_active: true,
+        max_allocation: 0,
+        total_allocated: 0,
+        unlock_at: 0,
+        expires_at: 0,
+        last_allocated_at: 0,
+        tier: 0,
+        weight: 1,
+        added_by,
+        added_at,
+        fund_id,
+        period_limit: 0,
+        period_secs: 0,
+        period_start: 0,
+        spent_in_period: 0,
+        suspended: false,
+        suspension_reason: String::new(),
+        suspended_at: 0,
+        can_deposit: false,
+        allocation_count: 0,
+        last_allocation_amount: 0,
+        target_kind,
+    }
+}
+
+// Creates a program-owned PDA the way Anchor's `init` does. The address is
+// predictable, so anyone can send it lamports first; in that case the rent is
+// topped up and the account allocated and assigned instead of created.
+fn create_pda_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+    
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program,
+                system_program::CreateAccount { from: payer, to: account },
+                &[signer_seeds],
+            ),
+            rent_exempt_minimum,
+            space as u64,
+            &crate::ID,
+        );
+    }
+    
+    let top_up = rent_exempt_minimum.saturating_sub(current_lamports);
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer, to: account.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: account.clone() },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program,
+            system_program::Assign { account_to_assign: account },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+// Pays each (whitelist_entry, to_token_account) pair in `remaining_accounts`
+// the matching amount. Shared by allocate_funds_batch and distribute_pro_rata,
+// which have already checked the signer and the number of pairs.
+fn allocate_to_pairs<'info>(
+    accounts: &mut AllocateFundsBatch<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    bumps: &AllocateFundsBatchBumps,
+    amounts: &[u64],
+) -> Result<()> {
+    let fund_account = &mut accounts.fund_account;
+    
+    require!(
+        !amounts.iter().any(|amount| requires_approval(fund_account, *amount)),
+        FundError::AllocationRequiresApproval
+    );
+    
+    let batch_total = amounts
+        .iter()
+        .try_fold(0u64, |total, amount| total.checked_add(*amount))
+        .ok_or(error!(FundError::MathOverflow))?;
+    require!(
+        fund_account.total_funds >= batch_total,
+        FundError::InsufficientFunds
+    );
+    
+    let timestamp = Clock::get()?.unix_timestamp;
+    
+    // Every paid pair counts against the signer's hourly allowance
+    let paid = amounts.iter().filter(|amount| **amount > 0).count() as u32;
+    record_signer_allocations(
+        fund_account,
+        accounts.signer_rate_limit.as_mut(),
+        bumps.signer_rate_limit,
+        accounts.authority.key(),
+        paid,
+        timestamp,
+    )?;
+    
+    for (pair, &amount) in remaining_accounts.chunks(2).zip(amounts.iter()) {
+        // Nothing to pay, e.g. a zero-weight entry in distribute_pro_rata
+        if amount == 0 {
+            continue;
+        }
+        
+        let mut whitelist_entry = Account::<WhitelistEntry>::try_from(&pair[0])?;
+        let to_token_account = InterfaceAccount::<TokenAccount>::try_from(&pair[1])?;
+        
+        let total_allocated = check_allocation(
+            fund_account,
+            &whitelist_entry,
+            &to_token_account,
+            &accounts.fund_token_account,
+            amount,
+            timestamp,
+        )?;
+        record_daily_allocation(fund_account, amount, timestamp)?;
+
+        let fee = transfer_with_fee(
+            fund_account,
+            &accounts.fund_token_account,
+            &accounts.mint,
+            accounts.fee_token_account.as_ref(),
+            to_token_account.to_account_info(),
+            &accounts.token_program,
+            amount,
+        )?;
+
+        fund_account.total_funds = fund_account
+            .total_funds
+            .checked_sub(amount)
+            .ok_or(error!(FundError::MathUnderflow))?;
+        fund_account.total_allocated = fund_account
+            .total_allocated
+            .checked_add(amount)
+            .ok_or(error!(FundError::MathOverflow))?;
+        
+        // Persist immediately so a recipient listed twice sees its updated total
+        whitelist_entry.total_allocated = total_allocated;
+        record_entry_allocation(&mut whitelist_entry, amount, timestamp)?;
+        whitelist_entry.exit(&crate::ID)?;
+
+        record_allocation(
+            &mut accounts.allocation_log,
+            whitelist_entry.address,
+            amount,
+            timestamp,
+        );
+
+        emit!(FundsAllocated {
+            recipient: whitelist_entry.address,
+            vault: accounts.fund_token_account.key(),
+            amount,
+            fee,
+            new_total: fund_account.total_funds,
+            total_allocated: fund_account.total_allocated,
+            memo: String::new(),
+            reason_code: None,
+            valid_until: 0,
+            allocation_count: whitelist_entry.allocation_count,
+            timestamp,
+        });
+    }
+    
+    // One record covers the whole batch
+    record_audit(
+        &mut accounts.fund_account,
+        accounts.audit_record.as_mut(),
+        bumps.audit_record,
+        AuditAction::BatchAllocation,
+        accounts.authority.key(),
+        Pubkey::default(),
+        batch_total,
+    )?;
+    
+    Ok(())
+}
+
+// What the recipient has spent in the budget period containing `now`; a
+// period that has run its course counts as empty
+fn spent_in_current_period(whitelist_entry: &WhitelistEntry, now: i64) -> u64 {
+    if now.saturating_sub(whitelist_entry.period_start) >= whitelist_entry.period_secs {
+        0
+    } else {
+        whitelist_entry.spent_in_period
+    }
+}
+
+// Per-recipient bookkeeping after a payout: when, how much, how often, and
+// the spend against its budget period
+fn record_entry_allocation(whitelist_entry: &mut WhitelistEntry, amount: u64, now: i64) -> Result<()> {
+    whitelist_entry.last_allocated_at = now;
+    whitelist_entry.last_allocation_amount = amount;
+    whitelist_entry.allocation_count = whitelist_entry
+        .allocation_count
+        .checked_add(1)
+        .ok_or(error!(FundError::MathOverflow))?;
+    record_period_spend(whitelist_entry, amount, now);
+    
+    Ok(())
+}
+
+// Counts `count` allocations by `signer` against max_allocations_per_hour.
+// Windows are fixed buckets: the first allocation once allocation_rate_window_secs
+// have passed since window_start opens a new window with a fresh count. The
+// signer's PDA is optional while the limit is off (0).
+fn record_signer_allocations(
+    fund_account: &Account<FundAccount>,
+    rate_limit: Option<&mut Account<SignerRateLimit>>,
+    bump: Option<u8>,
+    signer: Pubkey,
+    count: u32,
+    now: i64,
+) -> Result<()> {
+    let Some(rate_limit) = rate_limit else {
+        require!(
+            fund_account.max_allocations_per_hour == 0,
+            FundError::SignerRateLimitRequired
+        );
+        return Ok(());
+    };
+    
+    if now.saturating_sub(rate_limit.window_start) >= fund_account.allocation_rate_window_secs {
+        rate_limit.window_start = now;
+        rate_limit.allocations_in_window = 0;
+    }
+    rate_limit.allocations_in_window = rate_limit
+        .allocations_in_window
+        .checked_add(count)
+        .ok_or(error!(FundError::MathOverflow))?;
+    require!(
+        fund_account.max_allocations_per_hour == 0
+            || rate_limit.allocations_in_window <= fund_account.max_allocations_per_hour,
+        FundError::AllocationRateLimitExceeded
+    );
+    
+    rate_limit.fund = fund_account.key();
+    rate_limit.signer = signer;
+    rate_limit.last_allocation = now;
+    rate_limit.bump = bump.ok_or(error!(FundError::SignerRateLimitRequired))?;
+    
+    Ok(())
+}
+
+// Rolls the recipient's budget period over lazily, starting the new period
+// at `now`, and counts `amount` against it. Spending is tracked even without
+// a period_limit so that setting one takes effect against the current period.
+fn record_period_spend(whitelist_entry: &mut WhitelistEntry, amount: u64, now: i64) {
+    if now.saturating_sub(whitelist_entry.period_start) >= whitelist_entry.period_secs {
+        whitelist_entry.period_start = now;
+        whitelist_entry.spent_in_period = 0;
+    }
+    whitelist_entry.spent_in_period = whitelist_entry.spent_in_period.saturating_add(amount);
+}
+
+// Rolls the 24h window over once it has expired and counts `amount`
+// against it. Usage is tracked even while the limit is disabled (0) so
+// that enabling it mid-window accounts for what already left the fund.
+fn record_daily_allocation(fund_account: &mut FundAccount, amount: u64, now: i64) -> Result<()> {
+    if now.saturating_sub(fund_account.window_start) >= DAILY_WINDOW_SECS {
+        fund_account.window_start = now;
+        fund_account.allocated_in_window = 0;
+    }
+    
+    fund_account.allocated_in_window = fund_account
+        .allocated_in_window
+        .checked_add(amount)
+        .ok_or(error!(FundError::MathOverflow))?;
+    require!(
+        fund_account.daily_limit == 0 || fund_account.allocated_in_window <= fund_account.daily_limit,
+        FundError::DailyLimitExceeded
+    );
+    
+    Ok(())
+}
+
+// Pays `amount` out of the vault, skimming the fund's fee to the fee
+// destination first. Returns the fee taken.
+fn transfer_with_fee<'info>(
+    fund_account: &Account<'info, FundAccount>,
+    fund_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    fee_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    to: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let fee = u64::try_from(
+        (amount as u128)
+            .checked_mul(fund_account.fee_bps as u128)
+            .ok_or(error!(FundError::MathOverflow))?
+            / MAX_FEE_BPS as u128,
+    )
+    .map_err(|_| error!(FundError::MathOverflow))?;
+    let net_amount = amount
+        .checked_sub(fee)
+        .ok_or(error!(FundError::MathUnderflow))?;
+
+    if fee > 0 {
+        let fee_token_account = fee_token_account.ok_or(error!(FundError::FeeAccountRequired))?;
+        transfer_from_vault(
+            fund_account,
+            fund_token_account,
+            mint,
+            fee_token_account.to_account_info(),
+            token_program,
+            fee,
+        )?;
+    }
+
+    if net_amount > 0 {
+        transfer_from_vault(
+            fund_account,
+            fund_token_account,
+            mint,
+            to,
+            token_program,
+            net_amount,
+        )?;
+    }
+
+    Ok(fee)
+}
+
+#[derive(Accounts)]
+#[instruction(fund_id: [u8; 16])]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + FundAccount::INIT_SPACE,
+        seeds = [b"fund_account", fund_id.as_ref()],
+        bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        token::mint = mint,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AllocationLog::INIT_SPACE,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StoreFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub from_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + Depositor::INIT_SPACE,
+        seeds = [b"depositor", fund_account.key().as_ref(), from_token_account.owner.as_ref()],
+        bump
+    )]
+    pub depositor: Account<'info, Depositor>,
+    
+    #[account(
+        seeds = [b"whitelist", fund_account.fund_id.as_ref(), from_token_account.owner.as_ref()],
+        bump
+    )]
+    pub depositor_entry: Option<Account<'info, WhitelistEntry>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AllocateFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        constraint = to_token_account.mint == fund_account.mint @ FundError::MintMismatch,
+        constraint = to_token_account.key() != fund_token_account.key() @ FundError::SelfAllocationForbidden,
+        constraint = to_token_account.owner != fund_account.key() @ FundError::SelfAllocationForbidden
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.target_kind.bound_key(&to_token_account).as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        seeds = [b"operator", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump = operator.bump
+    )]
+    pub operator: Option<Account<'info, Operator>>,
+    
+    #[account(
+        seeds = [b"delegation", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Option<Account<'info, DelegatedAuthority>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Option<Program<'info, Memo>>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SignerRateLimit::INIT_SPACE,
+        seeds = [b"signer_rate", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub signer_rate_limit: Option<Account<'info, SignerRateLimit>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AllocateFundsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"operator", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump = operator.bump
+    )]
+    pub operator: Option<Account<'info, Operator>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SignerRateLimit::INIT_SPACE,
+        seeds = [b"signer_rate", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub signer_rate_limit: Option<Account<'info, SignerRateLimit>>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recovery_token_account.owner == fund_account.recovery_address @ FundError::InvalidRecoveryAddress
+    )]
+    pub recovery_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyDrain<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recovery_token_account.owner == fund_account.recovery_address @ FundError::InvalidRecoveryAddress
+    )]
+    pub recovery_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub current_admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = current_admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub new_admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = new_admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAdminProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub current_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct AddWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + WhitelistEntry::INIT_SPACE,
+        seeds = [b"whitelist", fund_account.fund_id.as_ref(), address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ToggleWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateWhitelistLabel<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SuspendWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReactivateWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetUnlockTime<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAllocationCap<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPeriodBudget<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllocationCooldown<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFee<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetDailyLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct AddOperator<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + Operator::INIT_SPACE,
+        seeds = [b"operator", fund_account.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub operator_account: Account<'info, Operator>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveOperator<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", fund_account.key().as_ref(), operator_account.operator.as_ref()],
+        bump = operator_account.bump
+    )]
+    pub operator_account: Account<'info, Operator>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetApprovalConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, recipient: Pubkey)]
+pub struct ProposeAllocation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        seeds = [b"whitelist", fund_account.fund_id.as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AllocationProposal::INIT_SPACE,
+        seeds = [b"proposal", fund_account.key().as_ref(), &fund_account.proposal_nonce.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, AllocationProposal>,
+    
+    #[account(
+        seeds = [b"operator", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump = operator.bump
+    )]
+    pub operator: Option<Account<'info, Operator>>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAllocation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        constraint = to_token_account.mint == fund_account.mint @ FundError::MintMismatch,
+        constraint = to_token_account.key() != fund_token_account.key() @ FundError::SelfAllocationForbidden,
+        constraint = to_token_account.owner != fund_account.key() @ FundError::SelfAllocationForbidden
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        address = proposal.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"proposal", fund_account.key().as_ref(), &proposal.nonce.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, AllocationProposal>,
+    
+    /// CHECK: Only receives the proposal's rent; must match proposal.proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub approver: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = approver,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAllocation<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"proposal", fund_account.key().as_ref(), &proposal.nonce.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, AllocationProposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinAllocation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMinDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdminThreshold<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateAdminOperation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AdminOperation::INIT_SPACE,
+        seeds = [b"admin_op", fund_account.key().as_ref(), &fund_account.admin_op_nonce.to_le_bytes()],
+        bump
+    )]
+    pub operation: Account<'info, AdminOperation>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAdminOperation<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"admin_op", fund_account.key().as_ref(), &operation.nonce.to_le_bytes()],
+        bump = operation.bump
+    )]
+    pub operation: Account<'info, AdminOperation>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAdminOperation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"admin_op", fund_account.key().as_ref(), &operation.nonce.to_le_bytes()],
+        bump = operation.bump
+    )]
+    pub operation: Account<'info, AdminOperation>,
+    
+    /// CHECK: Only receives the operation's rent; must match operation.proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    // Only for actions that change a single whitelist entry
+    #[account(mut)]
+    pub whitelist_entry: Option<Account<'info, WhitelistEntry>>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAllocationOperation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        constraint = to_token_account.mint == fund_account.mint @ FundError::MintMismatch,
+        constraint = to_token_account.key() != fund_token_account.key() @ FundError::SelfAllocationForbidden,
+        constraint = to_token_account.owner != fund_account.key() @ FundError::SelfAllocationForbidden
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.target_kind.bound_key(&to_token_account).as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"admin_op", fund_account.key().as_ref(), &operation.nonce.to_le_bytes()],
+        bump = operation.bump
+    )]
+    pub operation: Account<'info, AdminOperation>,
+    
+    /// CHECK: Only receives the operation's rent; must match operation.proposer
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAdminOperation<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"admin_op", fund_account.key().as_ref(), &operation.nonce.to_le_bytes()],
+        bump = operation.bump
+    )]
+    pub operation: Account<'info, AdminOperation>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenewWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddWhitelistBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTierLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWhitelistTier<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetWhitelistWeight<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDepositor<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = depositor_wallet,
+        seeds = [b"depositor", fund_account.key().as_ref(), depositor.depositor.as_ref()],
+        bump = depositor.bump
+    )]
+    pub depositor: Account<'info, Depositor>,
+    
+    /// CHECK: Only receives the record's rent; must match depositor.depositor
+    #[account(mut, address = depositor.depositor)]
+    pub depositor_wallet: UncheckedAccount<'info>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Reconcile<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"depositor", fund_account.key().as_ref(), authority.key().as_ref()],
+        bump = depositor.bump
+    )]
+    pub depositor: Account<'info, Depositor>,
+    
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepositsLocked<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRestrictedDeposits<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllocationRateLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetOpenDeposits<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWhitelistCanDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetClaimGracePeriod<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ClaimAccount::INIT_SPACE,
+        seeds = [b"claim", fund_account.key().as_ref(), &fund_account.claim_nonce.to_le_bytes()],
+        bump
+    )]
+    pub claim: Account<'info, ClaimAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        has_one = recipient @ FundError::UnauthorizedClaimant,
+        seeds = [b"claim", fund_account.key().as_ref(), &claim.nonce.to_le_bytes()],
+        bump = claim.bump
+    )]
+    pub claim: Account<'info, ClaimAccount>,
+    
+    // Any account the recipient owns with the fund's mint
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = recipient
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        address = claim.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"claim", fund_account.key().as_ref(), &claim.nonce.to_le_bytes()],
+        bump = claim.bump
+    )]
+    pub claim: Account<'info, ClaimAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(total_amount: u64, start_ts: i64, end_ts: i64, recipient: Pubkey)]
+pub struct CreateVesting<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"whitelist", fund_account.fund_id.as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + VestingSchedule::INIT_SPACE,
+        seeds = [b"vesting", fund_account.key().as_ref(), &fund_account.vesting_nonce.to_le_bytes()],
+        bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(address = schedule.whitelist_entry @ FundError::WhitelistAddressMismatch)]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        has_one = recipient @ FundError::UnauthorizedClaimant,
+        seeds = [b"vesting", fund_account.key().as_ref(), &schedule.nonce.to_le_bytes()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = recipient
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = recipient,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        address = schedule.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", fund_account.key().as_ref(), &schedule.nonce.to_le_bytes()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AllocateLocked<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    // One open lock per recipient
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AllocationLock::INIT_SPACE,
+        seeds = [b"lock", fund_account.key().as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub lock: Account<'info, AllocationLock>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = fund_account,
+        token::token_program = token_program,
+        seeds = [b"lock_vault", lock.key().as_ref()],
+        bump
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockAllocation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"lock", fund_account.key().as_ref(), lock.recipient.as_ref()],
+        bump = lock.bump
+    )]
+    pub lock: Account<'info, AllocationLock>,
+    
+    #[account(
+        mut,
+        address = lock.vault
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recipient_token_account.owner == lock.recipient @ FundError::WhitelistAddressMismatch
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Only receives the lock's rent; must match lock.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLockedAllocation<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = lock.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"lock", fund_account.key().as_ref(), lock.recipient.as_ref()],
+        bump = lock.bump
+    )]
+    pub lock: Account<'info, AllocationLock>,
+    
+    #[account(
+        mut,
+        address = lock.vault
+    )]
+    pub lock_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Only receives the lock's rent; must match lock.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+#[derive(Accounts)]
+pub struct AllocateEscrowed<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + EscrowedAllocation::INIT_SPACE,
+        seeds = [b"escrow", fund_account.key().as_ref(), &fund_account.escrow_nonce.to_le_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowedAllocation>,
+    
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint,
+        token::authority = fund_account,
+        token::token_program = token_program,
+        seeds = [b"escrow_vault", escrow.key().as_ref()],
+        bump
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"escrow", fund_account.key().as_ref(), &escrow.nonce.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowedAllocation>,
+    
+    #[account(
+        mut,
+        address = escrow.vault
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = recipient_token_account.owner == escrow.recipient @ FundError::WhitelistAddressMismatch
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Only receives the escrow's rent; must match escrow.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = escrow.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"escrow", fund_account.key().as_ref(), &escrow.nonce.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowedAllocation>,
+    
+    #[account(
+        mut,
+        address = escrow.vault
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Only receives the escrow's rent; must match escrow.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+
+#[derive(Accounts)]
+pub struct CreateRecurring<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        seeds = [whitelist_entry.target_kind.seed_prefix(), fund_account.fund_id.as_ref(), whitelist_entry.address.as_ref()],
+        bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RecurringAllocation::INIT_SPACE,
+        seeds = [b"recurring", fund_account.key().as_ref(), &fund_account.recurring_nonce.to_le_bytes()],
+        bump
+    )]
+    pub schedule: Account<'info, RecurringAllocation>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteRecurring<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        has_one = payer,
+        seeds = [b"recurring", fund_account.key().as_ref(), &schedule.nonce.to_le_bytes()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, RecurringAllocation>,
+    
+    #[account(
+        mut,
+        address = schedule.whitelist_entry @ FundError::WhitelistAddressMismatch
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    
+    #[account(
+        mut,
+        constraint = to_token_account.mint == fund_account.mint @ FundError::MintMismatch,
+        constraint = to_token_account.key() != fund_token_account.key() @ FundError::SelfAllocationForbidden,
+        constraint = to_token_account.owner != fund_account.key() @ FundError::SelfAllocationForbidden
+    )]
+    pub to_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = fee_token_account.owner == fund_account.fee_destination @ FundError::InvalidFeeDestination
+    )]
+    pub fee_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Only receives the schedule's rent once exhausted; must match schedule.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRecurring<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"recurring", fund_account.key().as_ref(), &schedule.nonce.to_le_bytes()],
+        bump = schedule.bump
+    )]
+    pub schedule: Account<'info, RecurringAllocation>,
+    
+    /// CHECK: Only receives the schedule's rent; must match schedule.payer
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverToken<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(constraint = mint.key() != fund_account.mint @ FundError::CannotRecoverFundMint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = source_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub source_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SweepLamports<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    /// CHECK: Only receives lamports; chosen by the admin
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFund<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(address = fund_account.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = fund_token_account.key() == fund_account.vault @ FundError::VaultMismatch,
+        constraint = fund_token_account.owner == fund_account.key() @ FundError::VaultMismatch
+    )]
+    pub fund_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"allocation_log", fund_account.key().as_ref()],
+        bump = allocation_log.bump
+    )]
+    pub allocation_log: Account<'info, AllocationLog>,
+    
+    #[account(
+        mut,
+        token::mint = mint
+    )]
+    pub destination_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuditConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PruneAuditRecords<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct CreateDelegation<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
     #[account(
         init,
         payer = admin,
-        space = 8 + WhitelistEntry::INIT_SPACE,
-        seeds = [b"whitelist", address.as_ref()],
+        space = 8 + DelegatedAuthority::INIT_SPACE,
+        seeds = [b"delegation", fund_account.key().as_ref(), delegate.as_ref()],
         bump
     )]
-    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    pub delegation: Account<'info, DelegatedAuthority>,
     
     #[account(mut)]
     pub admin: Signer<'info>,
-    
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveWhitelist<'info> {
-    #[account(mut)]
+pub struct RevokeDelegation<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
     pub fund_account: Account<'info, FundAccount>,
     
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"delegation", fund_account.key().as_ref(), delegation.delegate.as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, DelegatedAuthority>,
+    
+    /// CHECK: Only receives the delegation's rent; must match delegation.payer
     #[account(mut)]
-    pub whitelist_entry: Account<'info, WhitelistEntry>,
+    pub payer: UncheckedAccount<'info>,
     
     pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ToggleWhitelist<'info> {
+pub struct SetAdminChangeDelay<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecountWhitelist<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, allocation_nonce: u64)]
+pub struct AllocateFundsWithNonce<'info> {
+    pub allocate: AllocateFunds<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AllocationNonce::INIT_SPACE,
+        seeds = [b"nonce", allocate.fund_account.key().as_ref(), &allocation_nonce.to_le_bytes()],
+        bump
+    )]
+    pub nonce_record: Option<Account<'info, AllocationNonce>>,
+    
+    // Same key as allocate.authority; init needs a payer at this level
+    #[account(mut, address = allocate.authority.key())]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAllocationNonce<'info> {
+    #[account(
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
     pub fund_account: Account<'info, FundAccount>,
     
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [b"nonce", fund_account.key().as_ref(), &nonce_record.nonce.to_le_bytes()],
+        bump = nonce_record.bump
+    )]
+    pub nonce_record: Account<'info, AllocationNonce>,
+    
+    /// CHECK: Only receives the nonce record's rent; must match nonce_record.payer
     #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddWhitelistTokenAccount<'info> {
+    #[account(
+        mut,
+        seeds = [b"fund_account", fund_account.fund_id.as_ref()],
+        bump = fund_account.bump
+    )]
+    pub fund_account: Account<'info, FundAccount>,
+    
+    #[account(
+        constraint = token_account.mint == fund_account.mint @ FundError::MintMismatch,
+        constraint = token_account.owner != fund_account.key() @ FundError::SelfAllocationForbidden
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + WhitelistEntry::INIT_SPACE,
+        seeds = [b"whitelist_account", fund_account.fund_id.as_ref(), token_account.key().as_ref()],
+        bump
+    )]
     pub whitelist_entry: Account<'info, WhitelistEntry>,
     
+    #[account(mut)]
     pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditRecord::INIT_SPACE,
+        seeds = [b"audit", fund_account.key().as_ref(), &fund_account.action_sequence.to_le_bytes()],
+        bump
+    )]
+    pub audit_record: Option<Account<'info, AuditRecord>>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct FundAccount {
     pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub recovery_address: Pubkey,
+    pub vault: Pubkey,
+    pub mint: Pubkey,
     pub total_funds: u64,
+    // Lifetime totals for reporting; never decremented
+    pub total_deposited: u64,
+    pub total_allocated: u64,
     pub bump: u8,
-    pub whitelist_count: u16,
+    // Entries currently active; whitelist_total_created counts every entry ever added
+    pub whitelist_active: u16,
+    pub paused: bool,
+    pub allocation_cooldown_secs: i64,
+    pub fee_bps: u16,
+    pub fee_destination: Pubkey,
+    pub daily_limit: u64,
+    pub window_start: i64,
+    pub allocated_in_window: u64,
+    pub approver: Pubkey,
+    pub approval_threshold: u64,
+    pub proposal_ttl_secs: i64,
+    pub proposal_nonce: u64,
+    pub min_allocation: u64,
+    #[max_len(4)]
+    pub co_admins: Vec<Pubkey>,
+    pub admin_threshold: u8,
+    pub admin_set_version: u32,
+    pub admin_op_nonce: u64,
+    pub tier_limits: [u64; MAX_TIERS],
+    pub deposits_locked: bool,
+    pub claim_nonce: u64,
+    pub claim_grace_secs: i64,
+    // Earmarked for open claims and vesting schedules; still in the vault
+    // but not in total_funds
+    pub total_claimable: u64,
+    pub vesting_nonce: u64,
+    pub recurring_nonce: u64,
+    pub min_deposit: u64,
+    // Seeds the fund PDA, so one deployment can host many funds
+    pub fund_id: [u8; 16],
+    // When set, audited instructions must supply their AuditRecord PDA
+    pub audit_enabled: bool,
+    pub audit_retention_secs: i64,
+    // Seeds the next AuditRecord; only advances when a record is written
+    pub action_sequence: u64,
+    pub admin_change_delay_secs: i64,
+    // When the pending admin may accept; 0 while nothing is pending
+    pub admin_change_effective_at: i64,
+    // A lowered delay waiting out the current one; applies at admin_delay_change_at
+    pub pending_admin_change_delay: i64,
+    pub admin_delay_change_at: i64,
+    // Only whitelisted addresses with can_deposit may store funds
+    pub restricted_deposits: bool,
+    pub whitelist_total_created: u32,
+    // When false only admins (and cleared depositors) may store funds
+    pub open_deposits: bool,
+    // Allocations one signer may make per rate window; 0 disables the limit
+    pub max_allocations_per_hour: u32,
+    // An hour unless the admin shortens or lengthens it
+    pub allocation_rate_window_secs: i64,
+    // Entries that still exist, active or not; only close_whitelist lowers it
+    pub whitelist_entries: u32,
+    // Locks and recurring schedules not yet released, cancelled or finished
+    pub open_locks: u32,
+    pub open_recurring: u32,
+    pub escrow_nonce: u64,
+    // Escrowed allocations not yet released or cancelled
+    pub open_escrows: u32,
 }
 
 #[account]
@@ -272,8 +7347,690 @@ pub struct WhitelistEntry {
     #[max_len(64)]
     pub label: String,
     pub is_active: bool,
+    pub max_allocation: u64,
+    pub total_allocated: u64,
+    pub unlock_at: i64,
+    pub expires_at: i64,
+    pub last_allocated_at: i64,
+    pub tier: u8,
+    pub weight: u16,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    pub fund_id: [u8; 16],
+    // Rolling budget: at most period_limit per period_secs; 0 disables it
+    pub period_limit: u64,
+    pub period_secs: i64,
+    pub period_start: i64,
+    pub spent_in_period: u64,
+    // Set by suspend_whitelist; blocks allocations independently of is_active
+    pub suspended: bool,
+    #[max_len(128)]
+    pub suspension_reason: String,
+    pub suspended_at: i64,
+    // Clears the address to deposit while restricted_deposits is on
+    pub can_deposit: bool,
+    pub allocation_count: u32,
+    pub last_allocation_amount: u64,
+    // What `address` names: the recipient wallet or one token account
+    pub target_kind: TargetKind,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct WhitelistBatchEntry {
+    pub address: Pubkey,
+    pub label: String,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Depositor {
+    pub fund: Pubkey,
+    pub depositor: Pubkey,
+    pub total_deposited: u64,
+    pub deposit_count: u32,
+    pub last_deposit_at: i64,
+    pub bump: u8,
+}
+
+// Time-boxed allocation rights; unlike an operator, a delegation lapses on
+// its own at expires_at
+#[account]
+#[derive(InitSpace)]
+pub struct DelegatedAuthority {
+    pub fund: Pubkey,
+    pub delegate: Pubkey,
+    pub expires_at: i64,
+    // 0 means no per-allocation cap
+    pub per_allocation_cap: u64,
+    pub created_by: Pubkey,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+// Marks an allocation nonce as used so a retried allocation fails instead
+// of paying twice
+#[account]
+#[derive(InitSpace)]
+pub struct AllocationNonce {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub created_at: i64,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+// Per-signer allocation counter, so a leaked key cannot drain the fund in a
+// burst of transactions before it is rotated
+#[account]
+#[derive(InitSpace)]
+pub struct SignerRateLimit {
+    pub fund: Pubkey,
+    pub signer: Pubkey,
+    pub window_start: i64,
+    pub allocations_in_window: u32,
+    pub last_allocation: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Operator {
+    pub fund: Pubkey,
+    pub operator: Pubkey,
+    pub is_active: bool,
     pub added_by: Pubkey,
     pub added_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimAccount {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub created_at: i64,
+    pub claimed: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VestingSchedule {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub revoked: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllocationLock {
+    pub fund: Pubkey,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EscrowedAllocation {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub release_after: i64,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RecurringAllocation {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub interval_secs: i64,
+    pub next_run_at: i64,
+    pub remaining_runs: u32,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllocationProposal {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub whitelist_entry: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub proposer: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AdminOperation {
+    pub fund: Pubkey,
+    pub nonce: u64,
+    pub action: AdminAction,
+    pub proposer: Pubkey,
+    // Bit i is set once the admin in approval slot i has signed off
+    pub approvals: u8,
+    pub admin_set_version: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AuditRecord {
+    pub fund: Pubkey,
+    pub sequence: u64,
+    pub action: AuditAction,
+    pub actor: Pubkey,
+    // Recipient, whitelisted address or admin acted on; default when not applicable
+    pub target: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+    pub payer: Pubkey,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum TargetKind {
+    // Any token account owned by `address` may receive
+    Owner,
+    // Only the token account at `address` may receive
+    TokenAccount,
+}
+
+impl TargetKind {
+    // Distinct prefixes keep the two kinds' PDAs apart even for the same pubkey
+    pub fn seed_prefix(&self) -> &'static [u8] {
+        match self {
+            TargetKind::Owner => b"whitelist",
+            TargetKind::TokenAccount => b"whitelist_account",
+        }
+    }
+    
+    // The pubkey an entry of this kind must hold to pay `token_account`
+    pub fn bound_key(&self, token_account: &InterfaceAccount<TokenAccount>) -> Pubkey {
+        match self {
+            TargetKind::Owner => token_account.owner,
+            TargetKind::TokenAccount => token_account.key(),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AuditAction {
+    Allocation,
+    BatchAllocation,
+    ProposeAdmin,
+    AcceptAdmin,
+    AddAdmin,
+    RemoveAdmin,
+    WhitelistAdded,
+    WhitelistRemoved,
+    WhitelistReactivated,
+    Pause,
+    Unpause,
+    ApproveAllocation,
+    ExecuteAllocationOperation,
+    ExecuteAdminOperation,
+    RecurringAllocation,
+    CreateClaim,
+    ClaimFunds,
+    CreateVesting,
+    LockedAllocation,
+    Withdraw,
+    EmergencyWithdraw,
+    EmergencyDrain,
+    SetThreshold,
+    WhitelistBatchAdded,
+    WhitelistClosed,
+    WhitelistExpired,
+    WhitelistSuspended,
+    WhitelistUnsuspended,
+    EscrowedAllocation,
+    ClaimVested,
+    UnlockAllocation,
+    RevokeVesting,
+    CancelClaim,
+    CancelLockedAllocation,
+    CreateRecurring,
+    CancelRecurring,
+    WhitelistLabelUpdated,
+    WhitelistRenewed,
+    WhitelistTierUpdated,
+    WhitelistWeightUpdated,
+    WhitelistDepositAccessUpdated,
+    WhitelistUnlockTimeUpdated,
+    WhitelistCapUpdated,
+    SetAuditConfig,
+    ReleaseEscrow,
+    CancelEscrow,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AdminAction {
+    AddAdmin { admin: Pubkey },
+    RemoveAdmin { admin: Pubkey },
+    SetThreshold { threshold: u8 },
+    ProposeAdmin { new_admin: Pubkey },
+    Allocate { recipient: Pubkey, amount: u64 },
+    SetPaused { paused: bool },
+    SetFee { fee_bps: u16, fee_destination: Pubkey },
+    SetDailyLimit { daily_limit: u64 },
+    SetDepositsLocked { locked: bool },
+    SetRestrictedDeposits { restricted: bool },
+    SetOpenDeposits { open: bool },
+    SetTierLimits { tier_limits: [u64; MAX_TIERS] },
+    SetAllocationCooldown { cooldown_secs: i64 },
+    SetMinAllocation { min_allocation: u64 },
+    SetMinDeposit { min_deposit: u64 },
+    UpdateAllocationCap { address: Pubkey, max_allocation: u64 },
+    SetPeriodBudget { address: Pubkey, period_limit: u64, period_secs: i64 },
+    SetAuditConfig { enabled: bool, retention_secs: i64 },
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllocationLog {
+    pub fund: Pubkey,
+    // Index of the slot the next allocation will be written to
+    pub head: u8,
+    pub entries: [AllocationRecord; ALLOCATION_LOG_SIZE],
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, InitSpace)]
+pub struct AllocationRecord {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsStored {
+    pub depositor: Pubkey,
+    pub authority: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub new_total: u64,
+    pub total_deposited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsAllocated {
+    pub recipient: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub new_total: u64,
+    pub total_allocated: u64,
+    pub memo: String,
+    pub reason_code: Option<u16>,
+    // 0 when the allocation carried no expiry
+    pub valid_until: i64,
+    // The recipient's payout count including this one
+    pub allocation_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsWithdrawn {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub new_total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundPausedEvent {
+    pub paused: bool,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawal {
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub recovery_address: Pubkey,
+    pub amount: u64,
+    pub new_total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyDrained {
+    pub fund: Pubkey,
+    pub admin: Pubkey,
+    pub vault: Pubkey,
+    pub recovery_address: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistEntrySuspended {
+    pub address: Pubkey,
+    pub reason: String,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistEntryUnsuspended {
+    pub address: Pubkey,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistLabelUpdated {
+    pub address: Pubkey,
+    pub old_label: String,
+    pub new_label: String,
+}
+
+#[event]
+pub struct WhitelistTierUpdated {
+    pub address: Pubkey,
+    pub old_tier: u8,
+    pub new_tier: u8,
+}
+
+#[event]
+pub struct DailyLimitUpdated {
+    pub old_limit: u64,
+    pub new_limit: u64,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorAdded {
+    pub fund: Pubkey,
+    pub operator: Pubkey,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorRemoved {
+    pub fund: Pubkey,
+    pub operator: Pubkey,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsReconciled {
+    pub old_total: u64,
+    pub new_total: u64,
+    pub surplus: u64,
+}
+
+#[event]
+pub struct VaultShortfallDetected {
+    pub old_total: u64,
+    pub new_total: u64,
+    pub shortfall: u64,
+}
+
+#[event]
+pub struct DepositWithdrawn {
+    pub depositor: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub remaining_deposit: u64,
+    pub new_total: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimCreated {
+    pub claim: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundsClaimed {
+    pub claim: Pubkey,
+    pub recipient: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimCancelled {
+    pub claim: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingCreated {
+    pub schedule: Pubkey,
+    pub recipient: Pubkey,
+    pub total_amount: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct VestedFundsClaimed {
+    pub schedule: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingRevoked {
+    pub schedule: Pubkey,
+    pub recipient: Pubkey,
+    pub vested: u64,
+    pub returned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockedAllocationCreated {
+    pub lock: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockedAllocationReleased {
+    pub lock: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LockedAllocationCancelled {
+    pub lock: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowCreated {
+    pub escrow: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub release_after: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowReleased {
+    pub escrow: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowCancelled {
+    pub escrow: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LamportsSwept {
+    pub fund: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensRecovered {
+    pub mint: Pubkey,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundClosed {
+    pub fund: Pubkey,
+    pub mint: Pubkey,
+    pub swept: u64,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuditRecordsPruned {
+    pub fund: Pubkey,
+    pub pruned: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationCreated {
+    pub fund: Pubkey,
+    pub delegate: Pubkey,
+    pub expires_at: i64,
+    pub per_allocation_cap: u64,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DelegationRevoked {
+    pub fund: Pubkey,
+    pub delegate: Pubkey,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminChangeScheduled {
+    pub fund: Pubkey,
+    pub current_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub effective_at: i64,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminChangeFinalized {
+    pub fund: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminChangeCancelled {
+    pub fund: Pubkey,
+    pub cancelled_admin: Pubkey,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminChangeDelayUpdated {
+    pub fund: Pubkey,
+    pub old_delay_secs: i64,
+    pub new_delay_secs: i64,
+    pub effective_at: i64,
+    pub actor: Pubkey,
+}
+
+#[event]
+pub struct AllocationRateLimitUpdated {
+    pub fund: Pubkey,
+    pub old_limit: u32,
+    pub new_limit: u32,
+    pub window_secs: i64,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OpenDepositsUpdated {
+    pub fund: Pubkey,
+    pub open_deposits: bool,
+    pub actor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistRecounted {
+    pub fund: Pubkey,
+    pub whitelist_active: u16,
+    pub whitelist_total_created: u32,
+    pub timestamp: i64,
 }
 
 #[error_code]
@@ -290,4 +8047,206 @@ pub enum FundError {
     WhitelistEntryNotActive,
     #[msg("Label exceeds maximum length of 64 characters")]
     LabelTooLong,
+    #[msg("Allocation would exceed the recipient's lifetime cap")]
+    AllocationCapExceeded,
+    #[msg("No admin handover is pending")]
+    NoPendingAdmin,
+    #[msg("Signer is not the pending admin")]
+    UnauthorizedPendingAdmin,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Arithmetic underflow")]
+    MathUnderflow,
+    #[msg("Token account is not the fund's vault")]
+    VaultMismatch,
+    #[msg("Fund is paused")]
+    FundPaused,
+    #[msg("Recipient token account mint does not match the fund's vault")]
+    MintMismatch,
+    #[msg("Batch exceeds the maximum number of recipients")]
+    BatchTooLarge,
+    #[msg("Batch requires a whitelist entry and token account per amount")]
+    InvalidBatchAccounts,
+    #[msg("Whitelist entry must be deactivated before it can be closed")]
+    WhitelistEntryStillActive,
+    #[msg("Destination is not owned by the fund's recovery address")]
+    InvalidRecoveryAddress,
+    #[msg("Whitelist entry is already active")]
+    WhitelistEntryAlreadyActive,
+    #[msg("Recipient's allocations are still locked")]
+    AllocationLocked,
+    #[msg("Allocation cooldown is still active for this recipient")]
+    AllocationCooldownActive,
+    #[msg("Allocation cooldown cannot be negative")]
+    InvalidCooldown,
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Fee cannot exceed 10000 basis points")]
+    InvalidFeeBps,
+    #[msg("A fee token account is required while a fee is configured")]
+    FeeAccountRequired,
+    #[msg("Fee token account is not owned by the fund's fee destination")]
+    InvalidFeeDestination,
+    #[msg("Allocation would exceed the fund's daily limit")]
+    DailyLimitExceeded,
+    #[msg("Allocation is at or above the approval threshold; propose it instead")]
+    AllocationRequiresApproval,
+    #[msg("Approver must be set and distinct from the admin and the proposer")]
+    InvalidApprover,
+    #[msg("Signer is not the fund's approver")]
+    UnauthorizedApprover,
+    #[msg("Allocation proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal TTL cannot be negative")]
+    InvalidProposalTtl,
+    #[msg("Allocation is below the fund's minimum allocation")]
+    BelowMinAllocation,
+    #[msg("Admin threshold is above 1; use an admin operation instead")]
+    ThresholdApprovalRequired,
+    #[msg("Admin operation has not collected enough approvals")]
+    InsufficientApprovals,
+    #[msg("Admin has already approved this operation")]
+    AdminOperationAlreadyApproved,
+    #[msg("The admin set changed after this operation was created")]
+    StaleAdminOperation,
+    #[msg("Admin action cannot be executed by this instruction")]
+    InvalidAdminAction,
+    #[msg("Address is already an admin")]
+    AdminAlreadyExists,
+    #[msg("Address is not a co-admin")]
+    AdminNotFound,
+    #[msg("Fund already has the maximum number of admins")]
+    TooManyAdmins,
+    #[msg("Admin threshold must be between 1 and the number of admins")]
+    InvalidAdminThreshold,
+    #[msg("Signer is neither an admin nor an active operator")]
+    UnauthorizedAllocator,
+    #[msg("Whitelist entry has expired")]
+    WhitelistExpired,
+    #[msg("Expiry must be 0 or in the future")]
+    InvalidExpiry,
+    #[msg("Batch lists the same address more than once")]
+    DuplicateBatchAddress,
+    #[msg("Fund must be paused first")]
+    FundNotPaused,
+    #[msg("Whitelist entry has not expired yet")]
+    WhitelistNotExpired,
+    #[msg("Whitelist tier must be below MAX_TIERS")]
+    InvalidTier,
+    #[msg("Allocation exceeds the recipient's tier limit")]
+    TierLimitExceeded,
+    #[msg("Recipients' weights sum to zero")]
+    ZeroTotalWeight,
+    #[msg("Depositor record still has a withdrawable balance")]
+    DepositorBalanceNotZero,
+    #[msg("Depositor withdrawals are locked")]
+    DepositsLocked,
+    #[msg("Withdrawal exceeds the depositor's remaining contribution")]
+    WithdrawalExceedsDeposit,
+    #[msg("Claim grace period cannot be negative")]
+    InvalidClaimGrace,
+    #[msg("Claim has already been settled")]
+    ClaimAlreadySettled,
+    #[msg("Claim grace period has not elapsed")]
+    ClaimGracePeriodActive,
+    #[msg("Only the claim's recipient can claim it")]
+    UnauthorizedClaimant,
+    #[msg("Vesting must end after it starts")]
+    InvalidVestingSchedule,
+    #[msg("Nothing has vested since the last claim")]
+    NothingVested,
+    #[msg("Vesting schedule has already been revoked")]
+    VestingAlreadyRevoked,
+    #[msg("Unlock time must be in the future")]
+    InvalidUnlockTime,
+    #[msg("Locked allocation has not reached its unlock time")]
+    LockNotReleased,
+    #[msg("Locked allocation is already unlocked and cannot be cancelled")]
+    LockAlreadyReleasable,
+    #[msg("Recurring allocations need a positive interval and at least one run")]
+    InvalidRecurringSchedule,
+    #[msg("Recurring allocation is not due yet")]
+    RecurringNotDue,
+    #[msg("The fund's own mint cannot be recovered")]
+    CannotRecoverFundMint,
+    #[msg("Deposit is below the fund's minimum")]
+    DepositTooSmall,
+    #[msg("Memo exceeds 128 bytes")]
+    MemoTooLong,
+    #[msg("Fund still holds or earmarks funds")]
+    FundNotEmpty,
+    #[msg("Close all whitelist entries before closing the fund")]
+    WhitelistNotEmpty,
+    #[msg("A destination is required to sweep the vault remainder")]
+    SweepDestinationRequired,
+    #[msg("Whitelist entry belongs to a different fund")]
+    WhitelistFundMismatch,
+    #[msg("Auditing is enabled; supply the next audit record account")]
+    AuditRecordRequired,
+    #[msg("Audit retention period cannot be negative")]
+    InvalidRetentionPeriod,
+    #[msg("Audit record belongs to another fund or payer")]
+    AuditRecordMismatch,
+    #[msg("Audit record is still within the retention period")]
+    AuditRecordTooRecent,
+    #[msg("Delegation must expire in the future")]
+    InvalidDelegationExpiry,
+    #[msg("Allocation would exceed the recipient's budget for this period")]
+    PeriodBudgetExceeded,
+    #[msg("A period budget needs a positive period length")]
+    InvalidBudgetPeriod,
+    #[msg("Admin change is still timelocked")]
+    AdminChangeTimelocked,
+    #[msg("Admin change delay cannot be negative")]
+    InvalidAdminChangeDelay,
+    #[msg("Whitelist entry is suspended")]
+    WhitelistSuspended,
+    #[msg("Whitelist entry is already suspended")]
+    WhitelistAlreadySuspended,
+    #[msg("Whitelist entry is not suspended")]
+    WhitelistNotSuspended,
+    #[msg("Suspension reason exceeds 128 characters")]
+    SuspensionReasonTooLong,
+    #[msg("Depositor is not whitelisted for deposits")]
+    DepositorNotWhitelisted,
+    #[msg("Whitelist counter is already zero")]
+    CounterUnderflow,
+    #[msg("Allocation nonce has already been used")]
+    DuplicateAllocationNonce,
+    #[msg("Allocation nonce must be nonzero when a nonce record is supplied")]
+    InvalidAllocationNonce,
+    #[msg("A nonce record is required for a nonzero allocation nonce")]
+    AllocationNonceRequired,
+    #[msg("Allocation nonce is still inside its retention window")]
+    AllocationNonceTooRecent,
+    #[msg("Allocation is past its valid_until time")]
+    AllocationExpired,
+    #[msg("Vault balance would fall below the caller's minimum")]
+    VaultBalanceBelowMinimum,
+    #[msg("Cannot allocate into an account owned by the fund")]
+    SelfAllocationForbidden,
+    #[msg("This operation needs a whitelist entry bound to a wallet owner")]
+    OwnerEntryRequired,
+    #[msg("Fund account holds no lamports above rent exemption")]
+    NothingToSweep,
+    #[msg("Deposits are limited to admins")]
+    DepositsRestricted,
+    #[msg("Signer has reached its allocations for this hour")]
+    AllocationRateLimitExceeded,
+    #[msg("Signer rate limit account is required while the limit is set")]
+    SignerRateLimitRequired,
+    #[msg("Rate window must be positive")]
+    InvalidRateWindow,
+    #[msg("Open claims or vesting schedules must be settled first")]
+    ClaimsOutstanding,
+    #[msg("Locked or escrowed allocations or recurring schedules are still open")]
+    OpenAllocationsRemain,
+    #[msg("Release time must be in the future")]
+    InvalidReleaseTime,
+    #[msg("Escrowed allocation has not reached its release time")]
+    EscrowNotReleased,
+    #[msg("Escrowed allocation is past its release time and cannot be cancelled")]
+    EscrowAlreadyReleasable,
+    #[msg("This admin action needs the whitelist entry it names")]
+    WhitelistEntryRequired,
 }
\ No newline at end of file
@@ -1,5 +1,10 @@
+// The IDL handlers that `#[program]` emits at the crate root still call
+// `AccountInfo::realloc`, so the allow cannot sit on a narrower item.
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
 declare_id!("DijcrEqNwGBDr1PfbxNDpwiRSc86RA339czMbqGtoUjY");
 
@@ -7,11 +12,36 @@ declare_id!("DijcrEqNwGBDr1PfbxNDpwiRSc86RA339czMbqGtoUjY");
 pub mod instance_registry {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, registration_fee: u64) -> Result<()> {
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        registration_fee: u64,
+        rate_limit_seconds: i64,
+        heartbeat_timeout: i64,
+        max_instances: u64,
+        min_heartbeat_interval: i64,
+    ) -> Result<()> {
+        require!(rate_limit_seconds >= 0, RegistryError::InvalidRateLimit);
+        require!(heartbeat_timeout >= 0, RegistryError::InvalidHeartbeatTimeout);
+        require!(min_heartbeat_interval >= 0, RegistryError::InvalidHeartbeatInterval);
+
         let registry = &mut ctx.accounts.registry;
         registry.admin = ctx.accounts.admin.key();
         registry.registration_fee = registration_fee;
+        registry.rate_limit_seconds = rate_limit_seconds;
+        registry.heartbeat_timeout = heartbeat_timeout;
+        registry.registrations_paused = false;
+        registry.max_instances = max_instances;
+        registry.refund_bps = 0;
         registry.total_instances = 0;
+        registry.active_instances = 0;
+        registry.open_instances = 0;
+        registry.max_per_authority = 0;
+        registry.min_heartbeat_interval = min_heartbeat_interval;
+        registry.fee_mint = None;
+        registry.max_per_window = 0;
+        registry.rate_window_seconds = DEFAULT_RATE_WINDOW_SECONDS;
+        registry.grace_period = 0;
+        registry.missed_heartbeat_threshold = 1;
         registry.bump = ctx.bumps.registry;
         Ok(())
     }
@@ -20,31 +50,94 @@ pub mod instance_registry {
         ctx: Context<RegisterInstance>,
         instance_id: [u8; 32],
         endpoint: String,
+        descriptor_hash: [u8; 32],
+        initial_heartbeat: Option<i64>,
     ) -> Result<()> {
+        require!(
+            !ctx.accounts.registry.registrations_paused,
+            RegistryError::RegistrationsPaused
+        );
         require!(endpoint.len() <= 200, RegistryError::EndpointTooLong);
+        require!(is_valid_endpoint(&endpoint), RegistryError::InvalidEndpoint);
+        require!(!ctx.accounts.blocked.is_blocked, RegistryError::AuthorityBlocked);
+        // init_if_needed hands back a live claim untouched
+        require!(
+            ctx.accounts.endpoint_claim.instance_id == [0u8; 32],
+            RegistryError::EndpointAlreadyRegistered
+        );
 
         let registry = &ctx.accounts.registry;
+
+        // A max_instances of 0 means unlimited. Closed records free their slot.
+        require!(
+            registry.max_instances == 0 || registry.open_instances < registry.max_instances,
+            RegistryError::MaxInstancesReached
+        );
         let clock = Clock::get()?;
 
         // Check rate limit
         let rate_limit = &mut ctx.accounts.rate_limit;
         if rate_limit.last_registration > 0 {
             require!(
-                clock.unix_timestamp - rate_limit.last_registration >= 60,
+                clock.unix_timestamp - rate_limit.last_registration >= registry.rate_limit_seconds,
                 RegistryError::RateLimitExceeded
             );
         }
 
-        // Collect registration fee
-        let fee_transfer = system_program::Transfer {
-            from: ctx.accounts.authority.to_account_info(),
-            to: ctx.accounts.registry.to_account_info(),
-        };
-        let fee_ctx = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            fee_transfer,
+        // A max_per_authority of 0 means unlimited
+        require!(
+            registry.max_per_authority == 0 || rate_limit.active_count < registry.max_per_authority,
+            RegistryError::AuthorityQuotaExceeded
         );
-        system_program::transfer(fee_ctx, registry.registration_fee)?;
+
+        // Windowed limit on top of the per-registration gap. The window rolls
+        // over lazily on the first registration after it has elapsed.
+        if clock.unix_timestamp - rate_limit.window_start >= registry.rate_window_seconds {
+            rate_limit.window_start = clock.unix_timestamp;
+            rate_limit.registration_count = 0;
+        }
+        // A max_per_window of 0 means unlimited
+        require!(
+            registry.max_per_window == 0 || rate_limit.registration_count < registry.max_per_window,
+            RegistryError::WindowLimitExceeded
+        );
+
+        // Collect registration fee, in fee_mint tokens when one is set
+        match registry.fee_mint {
+            Some(fee_mint) => {
+                let (Some(mint), Some(fee_source), Some(fee_vault), Some(token_program)) = (
+                    &ctx.accounts.fee_mint,
+                    &ctx.accounts.fee_source,
+                    &ctx.accounts.fee_vault,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return err!(RegistryError::FeeAccountsRequired);
+                };
+                require_keys_eq!(mint.key(), fee_mint, RegistryError::FeeMintMismatch);
+
+                let fee_ctx = CpiContext::new(
+                    token_program.to_account_info(),
+                    TransferChecked {
+                        from: fee_source.to_account_info(),
+                        mint: mint.to_account_info(),
+                        to: fee_vault.to_account_info(),
+                        authority: ctx.accounts.authority.to_account_info(),
+                    },
+                );
+                token_interface::transfer_checked(fee_ctx, registry.registration_fee, mint.decimals)?;
+            }
+            None => {
+                let fee_transfer = system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.registry.to_account_info(),
+                };
+                let fee_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    fee_transfer,
+                );
+                system_program::transfer(fee_ctx, registry.registration_fee)?;
+            }
+        }
 
         // Create instance record
         let instance = &mut ctx.accounts.instance;
@@ -52,22 +145,72 @@ pub mod instance_registry {
         instance.authority = ctx.accounts.authority.key();
         instance.endpoint = endpoint;
         instance.registered_at = clock.unix_timestamp;
-        instance.last_heartbeat = clock.unix_timestamp;
-        instance.is_active = true;
+        // A future initial heartbeat gives a warming-up instance time before
+        // liveness checks can reap it
+        instance.last_heartbeat = match initial_heartbeat {
+            Some(initial) => {
+                require!(
+                    initial >= clock.unix_timestamp
+                        && initial - clock.unix_timestamp <= MAX_WARMUP_SECONDS,
+                    RegistryError::InvalidInitialHeartbeat
+                );
+                initial
+            }
+            None => clock.unix_timestamp,
+        };
+        instance.status = InstanceStatus::Active;
+        instance.current_load = 0;
+        instance.max_capacity = 0;
+        instance.missed_heartbeats = 0;
+        instance.descriptor_hash = descriptor_hash;
+        instance.fee_refunded = false;
+        instance.admin_deactivated = false;
+        instance.fee_paid = registry.registration_fee;
+        instance.fee_in_tokens = registry.fee_mint.is_some();
+        instance.last_miss_at = 0;
         instance.bump = ctx.bumps.instance;
 
         // Update rate limit
         rate_limit.authority = ctx.accounts.authority.key();
         rate_limit.last_registration = clock.unix_timestamp;
-        rate_limit.registration_count += 1;
+        rate_limit.registration_count = rate_limit
+            .registration_count
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
+        rate_limit.active_count = rate_limit
+            .active_count
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
         rate_limit.bump = ctx.bumps.rate_limit;
 
+        // First registration creates the (unblocked) blocklist record
+        let blocked = &mut ctx.accounts.blocked;
+        blocked.authority = ctx.accounts.authority.key();
+        blocked.bump = ctx.bumps.blocked;
+
+        let endpoint_claim = &mut ctx.accounts.endpoint_claim;
+        endpoint_claim.instance_id = instance_id;
+        endpoint_claim.bump = ctx.bumps.endpoint_claim;
+
         // Update registry stats
-        ctx.accounts.registry.total_instances += 1;
+        let registry = &mut ctx.accounts.registry;
+        registry.total_instances = registry
+            .total_instances
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
+        registry.open_instances = registry
+            .open_instances
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
+        registry.active_instances = registry
+            .active_instances
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
 
         emit!(InstanceRegistered {
             instance_id,
             authority: ctx.accounts.authority.key(),
+            descriptor_hash,
             timestamp: clock.unix_timestamp,
         });
 
@@ -78,13 +221,59 @@ pub mod instance_registry {
         let clock = Clock::get()?;
         let instance = &mut ctx.accounts.instance;
         
-        require!(instance.is_active, RegistryError::InstanceNotActive);
+        require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
         require!(
             ctx.accounts.authority.key() == instance.authority,
             RegistryError::UnauthorizedAccess
         );
+        // A min_heartbeat_interval of 0 disables throttling. An instance still
+        // inside its warm-up (initial heartbeat in the future) is not throttled.
+        require!(
+            instance.last_heartbeat > clock.unix_timestamp
+                || clock.unix_timestamp - instance.last_heartbeat >= ctx.accounts.registry.min_heartbeat_interval,
+            RegistryError::HeartbeatTooSoon
+        );
 
         instance.last_heartbeat = clock.unix_timestamp;
+        instance.missed_heartbeats = 0;
+
+        Ok(())
+    }
+
+    // Same checks as update_heartbeat for every record in remaining_accounts.
+    // Any failure aborts the whole batch so no instance is silently skipped.
+    pub fn update_heartbeat_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateHeartbeatBatch<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let accounts = ctx.remaining_accounts;
+        require!(
+            !accounts.is_empty() && accounts.len() <= MAX_HEARTBEAT_BATCH,
+            RegistryError::InvalidBatchSize
+        );
+
+        for (i, account) in accounts.iter().enumerate() {
+            require!(
+                !accounts[..i].iter().any(|other| other.key() == account.key()),
+                RegistryError::DuplicateBatchInstance
+            );
+
+            let mut instance = Account::<InstanceRecord>::try_from(account)?;
+            require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
+            require!(
+                ctx.accounts.authority.key() == instance.authority,
+                RegistryError::UnauthorizedAccess
+            );
+            require!(
+                instance.last_heartbeat > clock.unix_timestamp
+                    || clock.unix_timestamp - instance.last_heartbeat >= ctx.accounts.registry.min_heartbeat_interval,
+                RegistryError::HeartbeatTooSoon
+            );
+
+            instance.last_heartbeat = clock.unix_timestamp;
+            instance.missed_heartbeats = 0;
+            instance.exit(&crate::ID)?;
+        }
 
         Ok(())
     }
@@ -97,41 +286,683 @@ pub mod instance_registry {
             RegistryError::UnauthorizedAccess
         );
 
-        instance.is_active = false;
+        // Refund part of the fee this registration paid for a clean shutdown,
+        // capped so the registry stays rent-exempt. Already inactive instances
+        // get nothing, and token-denominated fees are not refunded in lamports.
+        // Reactivation is free, so the refund is paid at most once per registration.
+        let mut refunded = 0;
+        if instance.status != InstanceStatus::Inactive {
+            let registry = &ctx.accounts.registry;
+            let registry_info = registry.to_account_info();
+            let refund = if instance.fee_in_tokens || instance.fee_refunded {
+                0
+            } else {
+                (instance.fee_paid as u128 * registry.refund_bps as u128 / 10_000) as u64
+            };
+            let rent_exempt_minimum = Rent::get()?.minimum_balance(registry_info.data_len());
+            refunded = refund.min(registry_info.lamports().saturating_sub(rent_exempt_minimum));
+
+            if refunded > 0 {
+                **registry_info.try_borrow_mut_lamports()? -= refunded;
+                **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += refunded;
+                instance.fee_refunded = true;
+            }
+
+            let registry = &mut ctx.accounts.registry;
+            registry.active_instances = registry
+                .active_instances
+                .checked_sub(1)
+                .ok_or(RegistryError::InstanceCountUnderflow)?;
+        }
+
+        instance.status = InstanceStatus::Inactive;
 
         emit!(InstanceDeactivated {
             instance_id: instance.instance_id,
+            refunded,
+            admin_initiated: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Moderation path: the admin can deactivate any instance. No refund is
+    // paid, since the authority did not shut down cleanly.
+    pub fn admin_deactivate_instance(ctx: Context<AdminDeactivateInstance>) -> Result<()> {
+        let instance = &mut ctx.accounts.instance;
+
+        require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
+
+        instance.status = InstanceStatus::Inactive;
+        instance.admin_deactivated = true;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.active_instances = registry
+            .active_instances
+            .checked_sub(1)
+            .ok_or(RegistryError::InstanceCountUnderflow)?;
+
+        emit!(InstanceDeactivated {
+            instance_id: instance.instance_id,
+            refunded: 0,
+            admin_initiated: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_instance(ctx: Context<CloseInstance>) -> Result<()> {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+
+        let registry = &mut ctx.accounts.registry;
+        registry.open_instances = registry
+            .open_instances
+            .checked_sub(1)
+            .ok_or(RegistryError::InstanceCountUnderflow)?;
+        if ctx.accounts.instance.status != InstanceStatus::Inactive {
+            registry.active_instances = registry
+                .active_instances
+                .checked_sub(1)
+                .ok_or(RegistryError::InstanceCountUnderflow)?;
+        }
+
+        let rate_limit = &mut ctx.accounts.rate_limit;
+        rate_limit.active_count = rate_limit
+            .active_count
+            .checked_sub(1)
+            .ok_or(RegistryError::InstanceCountUnderflow)?;
+
+        Ok(())
+    }
+
+    // Reactivation is free: the registration fee is charged once per
+    // instance record, not per activation. Instances taken down by the
+    // admin, or owned by a blocked authority, stay down.
+    pub fn reactivate_instance(ctx: Context<ReactivateInstance>) -> Result<()> {
+        let clock = Clock::get()?;
+        let instance = &mut ctx.accounts.instance;
+        
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+        require!(instance.status == InstanceStatus::Inactive, RegistryError::InstanceAlreadyActive);
+        require!(!instance.admin_deactivated, RegistryError::InstanceAdminDeactivated);
+        require!(!ctx.accounts.blocked.is_blocked, RegistryError::AuthorityBlocked);
+
+        instance.status = InstanceStatus::Active;
+        instance.last_heartbeat = clock.unix_timestamp;
+        instance.missed_heartbeats = 0;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.active_instances = registry
+            .active_instances
+            .checked_add(1)
+            .ok_or(RegistryError::InstanceCountOverflow)?;
+
+        emit!(InstanceReactivated {
+            instance_id: instance.instance_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Moves a live instance between Active, Degraded and Draining. Going
+    // Inactive is left to deactivate_instance, which settles the refund
+    // and the active count.
+    pub fn set_status(ctx: Context<SetStatus>, status: InstanceStatus) -> Result<()> {
+        let instance = &mut ctx.accounts.instance;
+
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+        require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
+        require!(status != InstanceStatus::Inactive, RegistryError::InvalidStatus);
+
+        let old_status = std::mem::replace(&mut instance.status, status);
+
+        emit!(InstanceStatusChanged {
+            instance_id: instance.instance_id,
+            old_status,
+            new_status: status,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Lowering the capacity below the last reported load is allowed; the
+    // next report has to fit the new capacity
+    pub fn set_max_capacity(ctx: Context<SetMaxCapacity>, max_capacity: u32) -> Result<()> {
+        let instance = &mut ctx.accounts.instance;
+
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+
+        instance.max_capacity = max_capacity;
+
+        Ok(())
+    }
+
+    pub fn update_descriptor_hash(
+        ctx: Context<UpdateDescriptorHash>,
+        descriptor_hash: [u8; 32],
+    ) -> Result<()> {
+        let instance = &mut ctx.accounts.instance;
+
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+
+        let old_hash = instance.descriptor_hash;
+        instance.descriptor_hash = descriptor_hash;
+
+        emit!(DescriptorHashUpdated {
+            instance_id: instance.instance_id,
+            old_hash,
+            new_hash: descriptor_hash,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
+    pub fn report_load(ctx: Context<ReportLoad>, current_load: u32) -> Result<()> {
+        let instance = &mut ctx.accounts.instance;
+
+        require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+        require!(current_load <= instance.max_capacity, RegistryError::LoadExceedsCapacity);
+
+        instance.current_load = current_load;
+
+        emit!(InstanceLoadReported {
+            instance_id: instance.instance_id,
+            current_load,
+            max_capacity: instance.max_capacity,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_endpoint(ctx: Context<UpdateEndpoint>, endpoint: String) -> Result<()> {
+        require!(endpoint.len() <= 200, RegistryError::EndpointTooLong);
+        require!(is_valid_endpoint(&endpoint), RegistryError::InvalidEndpoint);
+
+        let instance = &mut ctx.accounts.instance;
+        
+        require!(
+            ctx.accounts.authority.key() == instance.authority,
+            RegistryError::UnauthorizedAccess
+        );
+
+        // The old claim is closed by the accounts struct; a claim already
+        // holding an id belongs to another instance (or this one, unchanged)
+        require!(
+            ctx.accounts.new_claim.instance_id == [0u8; 32],
+            RegistryError::EndpointAlreadyRegistered
+        );
+        ctx.accounts.new_claim.instance_id = instance.instance_id;
+        ctx.accounts.new_claim.bump = ctx.bumps.new_claim;
+
+        instance.endpoint = endpoint.clone();
+
+        emit!(EndpointUpdated {
+            instance_id: instance.instance_id,
+            endpoint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Read-only summary returned as return data (borsh-encoded RegistryStats),
+    // so dashboards and calling programs do not depend on RegistryConfig's layout
+    pub fn get_stats(ctx: Context<GetStats>) -> Result<RegistryStats> {
+        let registry = &ctx.accounts.registry;
+        Ok(RegistryStats {
+            total_instances: registry.total_instances,
+            active_instances: registry.active_instances,
+            registration_fee: registry.registration_fee,
+            rate_limit_seconds: registry.rate_limit_seconds,
+            open_instances: registry.open_instances,
+        })
+    }
+
+    // Anyone may call this to reap an instance that has stopped sending
+    // heartbeats. A stale instance is only deactivated once it has been found
+    // stale missed_heartbeat_threshold times, at most once per
+    // heartbeat_timeout; a heartbeat clears the count. Returns whether the
+    // instance is still active after the call.
+    pub fn check_liveness(ctx: Context<CheckLiveness>) -> Result<bool> {
+        let clock = Clock::get()?;
+        let registry = &ctx.accounts.registry;
+        let heartbeat_timeout = registry.heartbeat_timeout;
+        let deadline = heartbeat_timeout.saturating_add(registry.grace_period);
+        let threshold = registry.missed_heartbeat_threshold;
+        let instance = &mut ctx.accounts.instance;
+
+        require!(instance.status != InstanceStatus::Inactive, RegistryError::InstanceNotActive);
+
+        // A timeout of 0 disables reaping
+        if heartbeat_timeout == 0 || clock.unix_timestamp - instance.last_heartbeat <= deadline {
+            return Ok(true);
+        }
+
+        // Repeated calls within one interval would otherwise reap an instance
+        // the moment it goes stale
+        if instance.missed_heartbeats > 0 && clock.unix_timestamp - instance.last_miss_at < heartbeat_timeout {
+            return Ok(true);
+        }
+
+        instance.missed_heartbeats = instance.missed_heartbeats.saturating_add(1);
+        instance.last_miss_at = clock.unix_timestamp;
+        emit!(HeartbeatMissed {
+            instance_id: instance.instance_id,
+            missed_heartbeats: instance.missed_heartbeats,
+            timestamp: clock.unix_timestamp,
+        });
+        if instance.missed_heartbeats < threshold {
+            return Ok(true);
+        }
+
+        instance.status = InstanceStatus::Inactive;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.active_instances = registry
+            .active_instances
+            .checked_sub(1)
+            .ok_or(RegistryError::InstanceCountUnderflow)?;
+
+        emit!(InstanceDeactivated {
+            instance_id: instance.instance_id,
+            refunded: 0,
+            admin_initiated: false,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(false)
+    }
+
     pub fn update_registration_fee(ctx: Context<UpdateRegistry>, new_fee: u64) -> Result<()> {
         let registry = &mut ctx.accounts.registry;
+        let old_fee = registry.registration_fee;
         registry.registration_fee = new_fee;
+
+        emit!(RegistrationFeeUpdated {
+            old_fee,
+            new_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_rate_limit(ctx: Context<UpdateRegistry>, seconds: i64) -> Result<()> {
+        require!(seconds >= 0, RegistryError::InvalidRateLimit);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.rate_limit_seconds = seconds;
+        Ok(())
+    }
+
+    pub fn update_refund_bps(ctx: Context<UpdateRegistry>, refund_bps: u16) -> Result<()> {
+        require!(refund_bps <= 10_000, RegistryError::InvalidRefundBps);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.refund_bps = refund_bps;
+        Ok(())
+    }
+
+    pub fn update_max_instances(ctx: Context<UpdateRegistry>, max_instances: u64) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.max_instances = max_instances;
+        Ok(())
+    }
+
+    // Lowering the quota below an authority's current count only blocks
+    // its further registrations
+    pub fn update_max_per_authority(ctx: Context<UpdateRegistry>, max_per_authority: u32) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.max_per_authority = max_per_authority;
+        Ok(())
+    }
+
+    pub fn update_window_limit(
+        ctx: Context<UpdateRegistry>,
+        max_per_window: u32,
+        window_seconds: i64,
+    ) -> Result<()> {
+        require!(window_seconds > 0, RegistryError::InvalidRateWindow);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.max_per_window = max_per_window;
+        registry.rate_window_seconds = window_seconds;
         Ok(())
     }
+
+    // Only blocks new registrations; existing instances keep working
+    pub fn set_registrations_paused(ctx: Context<UpdateRegistry>, paused: bool) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.registrations_paused = paused;
+        Ok(())
+    }
+
+    pub fn update_liveness_policy(
+        ctx: Context<UpdateRegistry>,
+        grace_period: i64,
+        missed_heartbeat_threshold: u16,
+    ) -> Result<()> {
+        require!(grace_period >= 0, RegistryError::InvalidGracePeriod);
+        require!(missed_heartbeat_threshold > 0, RegistryError::InvalidMissedHeartbeatThreshold);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.grace_period = grace_period;
+        registry.missed_heartbeat_threshold = missed_heartbeat_threshold;
+        Ok(())
+    }
+
+    pub fn update_heartbeat_timeout(ctx: Context<UpdateRegistry>, seconds: i64) -> Result<()> {
+        require!(seconds >= 0, RegistryError::InvalidHeartbeatTimeout);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.heartbeat_timeout = seconds;
+        Ok(())
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        let registry_info = ctx.accounts.registry.to_account_info();
+        let admin_info = ctx.accounts.admin.to_account_info();
+
+        // The registry must stay rent-exempt after the withdrawal
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(registry_info.data_len());
+        let available = registry_info.lamports().saturating_sub(rent_exempt_minimum);
+        require!(amount <= available, RegistryError::InsufficientRent);
+
+        // The registry is program-owned, so lamports are moved directly
+        // rather than through a system transfer
+        **registry_info.try_borrow_mut_lamports()? -= amount;
+        **admin_info.try_borrow_mut_lamports()? += amount;
+
+        Ok(())
+    }
+
+    // Switches registration fees to fee_mint and creates the registry's vault
+    // for it. registration_fee is read in the new unit, so update it alongside.
+    pub fn set_fee_mint(ctx: Context<SetFeeMint>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let old_mint = registry.fee_mint;
+        registry.fee_mint = Some(ctx.accounts.fee_mint.key());
+
+        emit!(FeeMintUpdated {
+            old_mint,
+            new_mint: registry.fee_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Goes back to lamport fees. Tokens already collected stay withdrawable.
+    pub fn clear_fee_mint(ctx: Context<UpdateRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let old_mint = registry.fee_mint;
+        registry.fee_mint = None;
+
+        emit!(FeeMintUpdated {
+            old_mint,
+            new_mint: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_token_fees(ctx: Context<WithdrawTokenFees>, amount: u64) -> Result<()> {
+        let bump = ctx.accounts.registry.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"registry_config", &[bump]]];
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.fee_vault.to_account_info(),
+                    mint: ctx.accounts.fee_mint.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.registry.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.fee_mint.decimals,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn block_authority(ctx: Context<BlockAuthority>, authority: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let blocked = &mut ctx.accounts.blocked;
+        blocked.authority = authority;
+        blocked.is_blocked = true;
+        blocked.blocked_at = clock.unix_timestamp;
+        blocked.bump = ctx.bumps.blocked;
+
+        emit!(AuthorityBlockUpdated {
+            authority,
+            is_blocked: true,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unblock_authority(ctx: Context<UnblockAuthority>) -> Result<()> {
+        let blocked = &mut ctx.accounts.blocked;
+        blocked.is_blocked = false;
+
+        emit!(AuthorityBlockUpdated {
+            authority: blocked.authority,
+            is_blocked: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+fn endpoint_hash(endpoint: &str) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hash(endpoint.as_bytes()).to_bytes()
+}
+
+pub const DEFAULT_RATE_WINDOW_SECONDS: i64 = 3_600;
+
+// Furthest in the future register_instance may place the first heartbeat
+pub const MAX_WARMUP_SECONDS: i64 = 3_600;
+
+// Keeps a batch heartbeat comfortably inside the transaction account limit
+pub const MAX_HEARTBEAT_BATCH: usize = 20;
+
+// Accepts http(s):// and ws(s):// URLs with a non-empty host. Deliberately
+// minimal: no parsing crate, just a scan over the bytes.
+fn is_valid_endpoint(endpoint: &str) -> bool {
+    let rest = ["http://", "https://", "ws://", "wss://"]
+        .iter()
+        .find_map(|scheme| endpoint.strip_prefix(scheme));
+    let Some(rest) = rest else {
+        return false;
+    };
+
+    // Printable ASCII only, no spaces
+    if !rest.bytes().all(|b| b.is_ascii_graphic()) {
+        return false;
+    }
+
+    let host_len = rest
+        .bytes()
+        .position(|b| b == b'/' || b == b'?' || b == b'#')
+        .unwrap_or(rest.len());
+    let host = &rest[..host_len];
+    !host.is_empty() && !host.starts_with(':')
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RegistryConfig::INIT_SPACE,
+        seeds = [b"registry_config"],
+        bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(instance_id: [u8; 32], endpoint: String)]
+pub struct RegisterInstance<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InstanceRecord::INIT_SPACE,
+        seeds = [b"instance", instance_id.as_ref()],
+        bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimitAccount::INIT_SPACE,
+        seeds = [b"rate_limit", authority.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimitAccount>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + BlockedAuthority::INIT_SPACE,
+        seeds = [b"blocked", authority.key().as_ref()],
+        bump
+    )]
+    pub blocked: Account<'info, BlockedAuthority>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + EndpointClaim::INIT_SPACE,
+        seeds = [b"endpoint", endpoint_hash(&endpoint).as_ref()],
+        bump
+    )]
+    pub endpoint_claim: Account<'info, EndpointClaim>,
+    // Token fee accounts; only needed while registry.fee_mint is set
+    pub fee_mint: Option<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = fee_mint,
+        token::authority = authority
+    )]
+    pub fee_source: Option<InterfaceAccount<'info, TokenAccount>>,
+    // Must be the vault set_fee_mint created for the configured mint
+    #[account(
+        mut,
+        seeds = [b"fee_vault", registry.fee_mint.unwrap_or_default().as_ref()],
+        bump,
+        token::mint = fee_mint,
+        token::authority = registry
+    )]
+    pub fee_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHeartbeat<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHeartbeatBatch<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeactivateInstance<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct AdminDeactivateInstance<'info> {
     #[account(
-        init,
-        payer = admin,
-        space = 8 + RegistryConfig::INIT_SPACE,
+        mut,
         seeds = [b"registry_config"],
-        bump
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
     )]
     pub registry: Account<'info, RegistryConfig>,
-    #[account(mut)]
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
     pub admin: Signer<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(instance_id: [u8; 32])]
-pub struct RegisterInstance<'info> {
+pub struct CloseInstance<'info> {
     #[account(
         mut,
         seeds = [b"registry_config"],
@@ -139,39 +970,76 @@ pub struct RegisterInstance<'info> {
     )]
     pub registry: Account<'info, RegistryConfig>,
     #[account(
-        init,
-        payer = authority,
-        space = 8 + InstanceRecord::INIT_SPACE,
-        seeds = [b"instance", instance_id.as_ref()],
-        bump
+        mut,
+        close = authority,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
     )]
     pub instance: Account<'info, InstanceRecord>,
     #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + RateLimitAccount::INIT_SPACE,
+        mut,
         seeds = [b"rate_limit", authority.key().as_ref()],
-        bump
+        bump = rate_limit.bump
     )]
     pub rate_limit: Account<'info, RateLimitAccount>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"endpoint", endpoint_hash(&instance.endpoint).as_ref()],
+        bump = endpoint_claim.bump
+    )]
+    pub endpoint_claim: Account<'info, EndpointClaim>,
     #[account(mut)]
     pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateHeartbeat<'info> {
+pub struct ReactivateInstance<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
     #[account(
         mut,
         seeds = [b"instance", instance.instance_id.as_ref()],
         bump = instance.bump
     )]
     pub instance: Account<'info, InstanceRecord>,
+    // register_instance creates this for every authority it has seen
+    #[account(
+        seeds = [b"blocked", instance.authority.as_ref()],
+        bump = blocked.bump
+    )]
+    pub blocked: Account<'info, BlockedAuthority>,
     pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct DeactivateInstance<'info> {
+pub struct SetStatus<'info> {
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxCapacity<'info> {
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDescriptorHash<'info> {
     #[account(
         mut,
         seeds = [b"instance", instance.instance_id.as_ref()],
@@ -181,6 +1049,71 @@ pub struct DeactivateInstance<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ReportLoad<'info> {
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(endpoint: String)]
+pub struct UpdateEndpoint<'info> {
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"endpoint", endpoint_hash(&instance.endpoint).as_ref()],
+        bump = old_claim.bump
+    )]
+    pub old_claim: Account<'info, EndpointClaim>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + EndpointClaim::INIT_SPACE,
+        seeds = [b"endpoint", endpoint_hash(&endpoint).as_ref()],
+        bump
+    )]
+    pub new_claim: Account<'info, EndpointClaim>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetStats<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CheckLiveness<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"instance", instance.instance_id.as_ref()],
+        bump = instance.bump
+    )]
+    pub instance: Account<'info, InstanceRecord>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateRegistry<'info> {
     #[account(
@@ -193,12 +1126,133 @@ pub struct UpdateRegistry<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    pub fee_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"fee_vault", fee_mint.key().as_ref()],
+        bump,
+        token::mint = fee_mint,
+        token::authority = registry,
+        token::token_program = token_program
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenFees<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    pub fee_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"fee_vault", fee_mint.key().as_ref()],
+        bump
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, token::mint = fee_mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct BlockAuthority<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + BlockedAuthority::INIT_SPACE,
+        seeds = [b"blocked", authority.as_ref()],
+        bump
+    )]
+    pub blocked: Account<'info, BlockedAuthority>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockAuthority<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = registry.bump,
+        constraint = registry.admin == admin.key() @ RegistryError::UnauthorizedAccess
+    )]
+    pub registry: Account<'info, RegistryConfig>,
+    #[account(
+        mut,
+        seeds = [b"blocked", blocked.authority.as_ref()],
+        bump = blocked.bump
+    )]
+    pub blocked: Account<'info, BlockedAuthority>,
+    pub admin: Signer<'info>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct RegistryConfig {
     pub admin: Pubkey,
     pub registration_fee: u64,
+    pub rate_limit_seconds: i64,
+    pub heartbeat_timeout: i64,
+    pub registrations_paused: bool,
+    pub max_instances: u64,
+    pub refund_bps: u16,
+    // Lifetime registrations; closing a record does not lower it
     pub total_instances: u64,
+    // Currently active instances; total_instances minus this is churn
+    pub active_instances: u64,
+    // Instance records one authority may hold at once; 0 means unlimited
+    pub max_per_authority: u32,
+    pub min_heartbeat_interval: i64,
+    // Registration fees are charged in this token when set, else in lamports
+    pub fee_mint: Option<Pubkey>,
+    // Registrations one authority may make per rate window; 0 means unlimited
+    pub max_per_window: u32,
+    pub rate_window_seconds: i64,
+    // Slack past heartbeat_timeout before an instance counts as stale
+    pub grace_period: i64,
+    // Stale findings by check_liveness before an instance is deactivated
+    pub missed_heartbeat_threshold: u16,
+    // Instance records not yet closed, active or not; max_instances caps this
+    pub open_instances: u64,
     pub bump: u8,
 }
 
@@ -211,16 +1265,81 @@ pub struct InstanceRecord {
     pub endpoint: String,
     pub registered_at: i64,
     pub last_heartbeat: i64,
-    pub is_active: bool,
+    pub status: InstanceStatus,
+    // Self-reported by the authority for load balancers
+    pub current_load: u32,
+    pub max_capacity: u32,
+    // Stale findings since the last heartbeat
+    pub missed_heartbeats: u16,
+    // Hash of the service descriptor the endpoint serves, so clients can
+    // detect when it changes; the registry does not interpret it
+    pub descriptor_hash: [u8; 32],
+    // Set once deactivate_instance has paid a refund for this registration
+    pub fee_refunded: bool,
+    // Set by admin_deactivate_instance; the authority can no longer reactivate
+    pub admin_deactivated: bool,
+    // Fee charged at registration, in fee_mint tokens when fee_in_tokens is set
+    pub fee_paid: u64,
+    pub fee_in_tokens: bool,
+    // When check_liveness last counted a miss
+    pub last_miss_at: i64,
     pub bump: u8,
 }
 
+impl InstanceRecord {
+    // Whether consumers should route to the instance; Draining instances
+    // are still registered but should not receive new work
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, InstanceStatus::Active | InstanceStatus::Degraded)
+    }
+}
+
+// Fields are only ever appended, keeping get_stats' return data stable
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct RegistryStats {
+    pub total_instances: u64,
+    pub active_instances: u64,
+    pub registration_fee: u64,
+    pub rate_limit_seconds: i64,
+    pub open_instances: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum InstanceStatus {
+    Active,
+    Degraded,
+    Draining,
+    Inactive,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct RateLimitAccount {
     pub authority: Pubkey,
     pub last_registration: i64,
+    // Registrations since window_start
     pub registration_count: u32,
+    pub window_start: i64,
+    // Instance records currently held; closing one frees a quota slot
+    pub active_count: u32,
+    pub bump: u8,
+}
+
+// One per registered endpoint, so two instances cannot advertise the same
+// URL. Seeded by a hash because endpoints exceed the 32-byte seed limit.
+#[account]
+#[derive(InitSpace)]
+pub struct EndpointClaim {
+    pub instance_id: [u8; 32],
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedAuthority {
+    pub authority: Pubkey,
+    pub is_blocked: bool,
+    pub blocked_at: i64,
     pub bump: u8,
 }
 
@@ -228,12 +1347,81 @@ pub struct RateLimitAccount {
 pub struct InstanceRegistered {
     pub instance_id: [u8; 32],
     pub authority: Pubkey,
+    pub descriptor_hash: [u8; 32],
     pub timestamp: i64,
 }
 
 #[event]
 pub struct InstanceDeactivated {
     pub instance_id: [u8; 32],
+    pub refunded: u64,
+    // Set when the registry admin deactivated the instance for moderation
+    pub admin_initiated: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HeartbeatMissed {
+    pub instance_id: [u8; 32],
+    pub missed_heartbeats: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RegistrationFeeUpdated {
+    pub old_fee: u64,
+    pub new_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeMintUpdated {
+    pub old_mint: Option<Pubkey>,
+    pub new_mint: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityBlockUpdated {
+    pub authority: Pubkey,
+    pub is_blocked: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InstanceStatusChanged {
+    pub instance_id: [u8; 32],
+    pub old_status: InstanceStatus,
+    pub new_status: InstanceStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InstanceLoadReported {
+    pub instance_id: [u8; 32],
+    pub current_load: u32,
+    pub max_capacity: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InstanceReactivated {
+    pub instance_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EndpointUpdated {
+    pub instance_id: [u8; 32],
+    pub endpoint: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DescriptorHashUpdated {
+    pub instance_id: [u8; 32],
+    pub old_hash: [u8; 32],
+    pub new_hash: [u8; 32],
     pub timestamp: i64,
 }
 
@@ -247,4 +1435,58 @@ pub enum RegistryError {
     InstanceNotActive,
     #[msg("Unauthorized access.")]
     UnauthorizedAccess,
-}
+    #[msg("Withdrawal would leave the registry below the rent-exempt minimum.")]
+    InsufficientRent,
+    #[msg("Rate limit cannot be negative.")]
+    InvalidRateLimit,
+    #[msg("Heartbeat timeout cannot be negative.")]
+    InvalidHeartbeatTimeout,
+    #[msg("Instance is already active.")]
+    InstanceAlreadyActive,
+    #[msg("Registry instance count is already zero.")]
+    InstanceCountUnderflow,
+    #[msg("Registry instance count overflowed.")]
+    InstanceCountOverflow,
+    #[msg("New registrations are paused.")]
+    RegistrationsPaused,
+    #[msg("Maximum number of instances reached.")]
+    MaxInstancesReached,
+    #[msg("Endpoint must be an http(s) or ws(s) URL with a host.")]
+    InvalidEndpoint,
+    #[msg("Refund cannot exceed 10000 basis points.")]
+    InvalidRefundBps,
+    #[msg("Minimum heartbeat interval cannot be negative.")]
+    InvalidHeartbeatInterval,
+    #[msg("Heartbeat sent too soon after the previous one.")]
+    HeartbeatTooSoon,
+    #[msg("Authority is blocked from registering instances.")]
+    AuthorityBlocked,
+    #[msg("Authority has reached its instance quota.")]
+    AuthorityQuotaExceeded,
+    #[msg("Use deactivate_instance to mark an instance inactive.")]
+    InvalidStatus,
+    #[msg("Reported load exceeds the instance's capacity.")]
+    LoadExceedsCapacity,
+    #[msg("Batch must contain between 1 and 20 instances.")]
+    InvalidBatchSize,
+    #[msg("Instance appears more than once in the batch.")]
+    DuplicateBatchInstance,
+    #[msg("Token fee accounts are required while a fee mint is set.")]
+    FeeAccountsRequired,
+    #[msg("Fee mint does not match the registry's fee mint.")]
+    FeeMintMismatch,
+    #[msg("Endpoint is already registered to an instance.")]
+    EndpointAlreadyRegistered,
+    #[msg("Authority has reached its registrations for this window.")]
+    WindowLimitExceeded,
+    #[msg("Rate window must be positive.")]
+    InvalidRateWindow,
+    #[msg("Grace period cannot be negative.")]
+    InvalidGracePeriod,
+    #[msg("Missed heartbeat threshold must be at least 1.")]
+    InvalidMissedHeartbeatThreshold,
+    #[msg("Initial heartbeat must be between now and the maximum warm-up.")]
+    InvalidInitialHeartbeat,
+    #[msg("Instance was deactivated by the registry admin.")]
+    InstanceAdminDeactivated,
+}
\ No newline at end of file
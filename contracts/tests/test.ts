@@ -1,9 +1,11 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { FundManager } from "../target/types/fund_manager";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID, createMint, createAccount, mintTo, getAccount, getAssociatedTokenAddress, createAssociatedTokenAccount } from "@solana/spl-token";
+import { InstanceRegistry } from "../target/types/instance_registry";
+import { PublicKey, Keypair, SystemProgram, SYSVAR_CLOCK_PUBKEY, Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, ExtensionType, createMint, createAccount, mintTo, transfer, approve, getAccount, getMintLen, getAssociatedTokenAddress, createAssociatedTokenAccount, createInitializeMintInstruction, createInitializeTransferFeeConfigInstruction } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 describe("Fund Manager", () => {
   const provider = anchor.AnchorProvider.env();
@@ -14,6 +16,7 @@ describe("Fund Manager", () => {
   let user1: Keypair;
   let user2: Keypair;
   let newAdmin: Keypair;
+  let recovery: Keypair;
   let mint: PublicKey;
   let fundAccount: PublicKey;
   let fundAccountBump: number;
@@ -25,12 +28,41 @@ describe("Fund Manager", () => {
   const DEPOSIT_AMOUNT = 100_000_000; // 100 tokens
   const ALLOCATION_AMOUNT = 50_000_000; // 50 tokens
 
+  // Each fund PDA is seeded by a 16-byte id; whitelist entries are scoped to it
+  const newFundId = () => Array.from(Keypair.generate().publicKey.toBytes().slice(0, 16));
+  const fundId = newFundId();
+
+  const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+
+  // Decode the named program event emitted by a confirmed transaction
+  const findEvent = async (signature: string, name: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const events = [...eventParser.parseLogs(tx.meta.logMessages)];
+    return events.find((event) => event.name.toLowerCase() === name.toLowerCase());
+  };
+
+  // The validator's clock, which can lag the wall clock by a second or two
+  const chainTime = async () => {
+    const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY, "confirmed");
+    return Number(clock.data.readBigInt64LE(32));
+  };
+
+  const waitForChainTime = async (target: number) => {
+    while ((await chainTime()) < target) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+  };
+
   before(async () => {
     // Generate test keypairs
     admin = Keypair.generate();
     user1 = Keypair.generate();
     user2 = Keypair.generate();
     newAdmin = Keypair.generate();
+    recovery = Keypair.generate();
 
     // Airdrop SOL to test accounts
     const connection = provider.connection;
@@ -55,7 +87,7 @@ describe("Fund Manager", () => {
 
     // Find fund account PDA
     [fundAccount, fundAccountBump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("fund_account")],
+      [Buffer.from("fund_account"), Buffer.from(fundId)],
       program.programId
     );
 
@@ -77,9 +109,11 @@ describe("Fund Manager", () => {
   describe("Initialization", () => {
     it("should initialize fund manager successfully", async () => {
       await program.methods
-        .initialize(admin.publicKey)
+        .initialize(fundId, admin.publicKey, recovery.publicKey)
         .accounts({
           fundAccount,
+          mint,
+          fundTokenAccount,
           payer: admin.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -90,14 +124,18 @@ describe("Fund Manager", () => {
       expect(fundAccountData.admin.toString()).to.equal(admin.publicKey.toString());
       expect(fundAccountData.totalFunds.toNumber()).to.equal(0);
       expect(fundAccountData.bump).to.equal(fundAccountBump);
+      expect(fundAccountData.vault.toString()).to.equal(fundTokenAccount.toString());
+      expect(fundAccountData.mint.toString()).to.equal(mint.toString());
     });
 
     it("should fail to initialize twice", async () => {
       try {
         await program.methods
-          .initialize(admin.publicKey)
+          .initialize(fundId, admin.publicKey, recovery.publicKey)
           .accounts({
             fundAccount,
+            mint,
+            fundTokenAccount,
             payer: admin.publicKey,
             systemProgram: SystemProgram.programId,
           })
@@ -115,17 +153,18 @@ describe("Fund Manager", () => {
       const initialUserBalance = await getAccount(provider.connection, user1TokenAccount);
       const initialFundBalance = await getAccount(provider.connection, fundTokenAccount);
 
-      await program.methods
+      const signature = await program.methods
         .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
         .accounts({
           fundAccount,
+          mint,
           fromTokenAccount: user1TokenAccount,
           fundTokenAccount,
           authority: user1.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([user1])
-        .rpc();
+        .rpc({ commitment: "confirmed" });
 
       const finalUserBalance = await getAccount(provider.connection, user1TokenAccount);
       const finalFundBalance = await getAccount(provider.connection, fundTokenAccount);
@@ -134,6 +173,14 @@ describe("Fund Manager", () => {
       expect(Number(initialUserBalance.amount) - Number(finalUserBalance.amount)).to.equal(DEPOSIT_AMOUNT);
       expect(Number(finalFundBalance.amount) - Number(initialFundBalance.amount)).to.equal(DEPOSIT_AMOUNT);
       expect(fundAccountData.totalFunds.toNumber()).to.equal(DEPOSIT_AMOUNT);
+      expect(fundAccountData.totalDeposited.toNumber()).to.equal(DEPOSIT_AMOUNT);
+
+      const event = await findEvent(signature, "FundsStored");
+      expect(event.data.depositor.toString()).to.equal(user1.publicKey.toString());
+      expect(event.data.vault.toString()).to.equal(fundTokenAccount.toString());
+      expect(event.data.amount.toNumber()).to.equal(DEPOSIT_AMOUNT);
+      expect(event.data.newTotal.toNumber()).to.equal(DEPOSIT_AMOUNT);
+      expect(event.data.totalDeposited.toNumber()).to.equal(DEPOSIT_AMOUNT);
     });
 
     it("should allow multiple users to store funds", async () => {
@@ -144,6 +191,7 @@ describe("Fund Manager", () => {
         .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
         .accounts({
           fundAccount,
+          mint,
           fromTokenAccount: user2TokenAccount,
           fundTokenAccount,
           authority: user2.publicKey,
@@ -159,6 +207,101 @@ describe("Fund Manager", () => {
       expect(fundAccountData.totalFunds.toNumber()).to.equal(initialTotalFunds + DEPOSIT_AMOUNT);
     });
 
+    it("should track each depositor's contributions", async () => {
+      const [depositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), fundAccount.toBuffer(), user2.publicKey.toBuffer()],
+        program.programId
+      );
+      const before = await program.account.depositor.fetchNullable(depositor);
+
+      await program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount: user2TokenAccount,
+          fundTokenAccount,
+          depositor,
+          authority: user2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user2])
+        .rpc();
+
+      const record = await program.account.depositor.fetch(depositor);
+      expect(record.depositor.toString()).to.equal(user2.publicKey.toString());
+      expect(record.fund.toString()).to.equal(fundAccount.toString());
+      expect(record.totalDeposited.toNumber()).to.equal((before ? before.totalDeposited.toNumber() : 0) + DEPOSIT_AMOUNT);
+      expect(record.depositCount).to.equal((before ? before.depositCount : 0) + 1);
+      expect(record.lastDepositAt.toNumber()).to.be.greaterThan(0);
+    });
+
+    it("should attribute a delegated deposit to the source account's owner", async () => {
+      const delegate = Keypair.generate();
+      const [ownerRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), fundAccount.toBuffer(), user1.publicKey.toBuffer()],
+        program.programId
+      );
+      const [delegateRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), fundAccount.toBuffer(), delegate.publicKey.toBuffer()],
+        program.programId
+      );
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(delegate.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+      await approve(provider.connection, user1, user1TokenAccount, delegate.publicKey, user1, DEPOSIT_AMOUNT);
+      const before = await program.account.depositor.fetch(ownerRecord);
+
+      const signature = await program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount: user1TokenAccount,
+          fundTokenAccount,
+          depositor: ownerRecord,
+          authority: delegate.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([delegate])
+        .rpc({ commitment: "confirmed" });
+
+      const record = await program.account.depositor.fetch(ownerRecord);
+      expect(record.depositor.toString()).to.equal(user1.publicKey.toString());
+      expect(record.totalDeposited.toNumber()).to.equal(before.totalDeposited.toNumber() + DEPOSIT_AMOUNT);
+      expect(await provider.connection.getAccountInfo(delegateRecord)).to.be.null;
+
+      const event = await findEvent(signature, "FundsStored");
+      expect(event.data.depositor.toString()).to.equal(user1.publicKey.toString());
+      expect(event.data.authority.toString()).to.equal(delegate.publicKey.toString());
+    });
+
+    it("should not let the admin close a depositor record with a balance", async () => {
+      const [depositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), fundAccount.toBuffer(), user2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .closeDepositor()
+          .accounts({
+            fundAccount,
+            depositor,
+            depositorWallet: user2.publicKey,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositorBalanceNotZero");
+      }
+    });
+
     it("should fail with insufficient user funds", async () => {
       const excessiveAmount = INITIAL_MINT_AMOUNT + 1;
       
@@ -167,6 +310,7 @@ describe("Fund Manager", () => {
           .storeFunds(new anchor.BN(excessiveAmount))
           .accounts({
             fundAccount,
+            mint,
             fromTokenAccount: user1TokenAccount,
             fundTokenAccount,
             authority: user1.publicKey,
@@ -181,299 +325,7882 @@ describe("Fund Manager", () => {
     });
   });
 
-  describe("Fund Allocation", () => {
-    let recipientAccount: PublicKey;
-    let recipient: Keypair;
-    let whitelistEntry: PublicKey;
+  describe("Multiple Funds", () => {
+    const otherFundId = newFundId();
+    let otherMint: PublicKey;
+    let otherFundAccount: PublicKey;
+    let otherFundTokenAccount: PublicKey;
+    let user1OtherTokenAccount: PublicKey;
 
-    beforeEach(async () => {
-      recipient = Keypair.generate();
-      recipientAccount = await createAssociatedTokenAccount(
-        provider.connection,
-        admin,
-        mint,
-        recipient.publicKey
-      );
-      
-      // Find whitelist entry PDA
-      [whitelistEntry] = PublicKey.findProgramAddressSync(
-        [Buffer.from("whitelist"), recipient.publicKey.toBuffer()],
+    before(async () => {
+      const connection = provider.connection;
+      otherMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      [otherFundAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(otherFundId)],
         program.programId
       );
-      
-      // Add recipient to whitelist
+      otherFundTokenAccount = await createAccount(connection, admin, otherMint, otherFundAccount, Keypair.generate());
+      user1OtherTokenAccount = await createAssociatedTokenAccount(connection, admin, otherMint, user1.publicKey);
+      await mintTo(connection, admin, otherMint, user1OtherTokenAccount, admin, INITIAL_MINT_AMOUNT);
+
       await program.methods
-        .addWhitelist(recipient.publicKey, "Test Recipient")
+        .initialize(otherFundId, admin.publicKey, recovery.publicKey)
         .accounts({
-          fundAccount,
-          whitelistEntry,
-          admin: admin.publicKey,
+          fundAccount: otherFundAccount,
+          mint: otherMint,
+          fundTokenAccount: otherFundTokenAccount,
+          payer: admin.publicKey,
           systemProgram: SystemProgram.programId,
         })
         .signers([admin])
         .rpc();
     });
 
-    it("should allow admin to allocate funds", async () => {
-      const initialFundBalance = await getAccount(provider.connection, fundTokenAccount);
-      const initialRecipientBalance = await getAccount(provider.connection, recipientAccount);
-      const initialTotalFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+    it("should keep balances separate between funds of different mints", async () => {
+      const primaryTotalBefore = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+      const primaryVaultBefore = await getAccount(provider.connection, fundTokenAccount);
 
       await program.methods
-        .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount: otherFundAccount,
+          mint: otherMint,
+          fromTokenAccount: user1OtherTokenAccount,
+          fundTokenAccount: otherFundTokenAccount,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      await program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
         .accounts({
           fundAccount,
+          mint,
+          fromTokenAccount: user1TokenAccount,
           fundTokenAccount,
-          toTokenAccount: recipientAccount,
-          whitelistEntry,
-          admin: admin.publicKey,
+          authority: user1.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([admin])
+        .signers([user1])
         .rpc();
 
-      const finalFundBalance = await getAccount(provider.connection, fundTokenAccount);
-      const finalRecipientBalance = await getAccount(provider.connection, recipientAccount);
-      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      const otherFundData = await program.account.fundAccount.fetch(otherFundAccount);
+      const otherVault = await getAccount(provider.connection, otherFundTokenAccount);
+      const primaryFundData = await program.account.fundAccount.fetch(fundAccount);
+      const primaryVaultAfter = await getAccount(provider.connection, fundTokenAccount);
 
-      expect(Number(initialFundBalance.amount) - Number(finalFundBalance.amount)).to.equal(ALLOCATION_AMOUNT);
-      expect(Number(finalRecipientBalance.amount) - Number(initialRecipientBalance.amount)).to.equal(ALLOCATION_AMOUNT);
-      expect(fundAccountData.totalFunds.toNumber()).to.equal(initialTotalFunds - ALLOCATION_AMOUNT);
+      expect(otherFundData.mint.toString()).to.equal(otherMint.toString());
+      expect(otherFundData.totalFunds.toNumber()).to.equal(DEPOSIT_AMOUNT);
+      expect(Number(otherVault.amount)).to.equal(DEPOSIT_AMOUNT);
+      expect(primaryFundData.totalFunds.toNumber()).to.equal(primaryTotalBefore + DEPOSIT_AMOUNT);
+      expect(Number(primaryVaultAfter.amount) - Number(primaryVaultBefore.amount)).to.equal(DEPOSIT_AMOUNT);
     });
 
-    it("should fail when non-admin tries to allocate funds", async () => {
+    it("should reject allocating into a token account of another mint", async () => {
+      const recipient = Keypair.generate();
+      const wrongMintAccount = await createAssociatedTokenAccount(provider.connection, admin, otherMint, recipient.publicKey);
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Wrong Mint Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
       try {
         await program.methods
           .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
           .accounts({
             fundAccount,
+            mint,
             fundTokenAccount,
-            toTokenAccount: recipientAccount,
+            toTokenAccount: wrongMintAccount,
             whitelistEntry,
-            admin: user1.publicKey,
+            authority: admin.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
-          .signers([user1])
+          .signers([admin])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("UnauthorizedAdmin");
+        expect(error.message).to.include("MintMismatch");
       }
     });
 
-    it("should fail when allocating more than available funds", async () => {
-      const currentFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
-      const excessiveAmount = currentFunds + 1;
-
+    it("should reject a token account of another fund's mint", async () => {
       try {
         await program.methods
-          .allocateFunds(new anchor.BN(excessiveAmount))
+          .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
           .accounts({
-            fundAccount,
-            fundTokenAccount,
-            toTokenAccount: recipientAccount,
-            whitelistEntry,
-            admin: admin.publicKey,
+            fundAccount: otherFundAccount,
+            mint: otherMint,
+            fromTokenAccount: user1TokenAccount,
+            fundTokenAccount: otherFundTokenAccount,
+            authority: user1.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
-          .signers([admin])
+          .signers([user1])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("InsufficientFunds");
+        expect(error.message).to.include("ConstraintTokenMint");
       }
     });
-  });
 
-  describe("Admin Management", () => {
-    it("should allow current admin to set new admin", async () => {
+    it("should host a second fund on the same mint with its own admin and whitelist", async () => {
+      const connection = provider.connection;
+      const sameMintFundId = newFundId();
+      const [sameMintFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(sameMintFundId)],
+        program.programId
+      );
+      const sameMintVault = await createAccount(connection, admin, mint, sameMintFund, Keypair.generate());
+
       await program.methods
-        .setAdmin(newAdmin.publicKey)
+        .initialize(sameMintFundId, user2.publicKey, recovery.publicKey)
         .accounts({
-          fundAccount,
-          currentAdmin: admin.publicKey,
+          fundAccount: sameMintFund,
+          mint,
+          fundTokenAccount: sameMintVault,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
         })
         .signers([admin])
         .rpc();
 
-      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
-      expect(fundAccountData.admin.toString()).to.equal(newAdmin.publicKey.toString());
-    });
-
-    it("should allow new admin to allocate funds", async () => {
       const recipient = Keypair.generate();
-      const recipientAccount = await createAssociatedTokenAccount(
-        provider.connection,
-        admin,
-        mint,
-        recipient.publicKey
+      const recipientTokenAccount = await createAssociatedTokenAccount(connection, admin, mint, recipient.publicKey);
+      const [sameMintEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(sameMintFundId), recipient.publicKey.toBuffer()],
+        program.programId
       );
-      
-      // Find whitelist entry PDA for new recipient
-      const [whitelistEntry] = PublicKey.findProgramAddressSync(
-        [Buffer.from("whitelist"), recipient.publicKey.toBuffer()],
+      const [primaryEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
         program.programId
       );
-      
-      // Add recipient to whitelist using new admin
+
       await program.methods
-        .addWhitelist(recipient.publicKey, "New Admin Test Recipient")
+        .addWhitelist(recipient.publicKey, "Second Fund Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
         .accounts({
-          fundAccount,
-          whitelistEntry,
-          admin: newAdmin.publicKey,
+          fundAccount: sameMintFund,
+          whitelistEntry: sameMintEntry,
+          admin: user2.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([newAdmin])
+        .signers([user2])
         .rpc();
 
-      const remainingFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
-      const allocationAmount = Math.min(10_000_000, remainingFunds); // 10 tokens or remaining funds
+      const fundData = await program.account.fundAccount.fetch(sameMintFund);
+      const entryData = await program.account.whitelistEntry.fetch(sameMintEntry);
+      expect(fundData.mint.toString()).to.equal(mint.toString());
+      expect(fundData.fundId).to.deep.equal(sameMintFundId);
+      expect(entryData.fundId).to.deep.equal(sameMintFundId);
+      expect(await connection.getAccountInfo(primaryEntry)).to.be.null;
 
-      if (allocationAmount > 0) {
+      // The second fund's admin has no say over the primary fund
+      try {
         await program.methods
-          .allocateFunds(new anchor.BN(allocationAmount))
+          .addWhitelist(recipient.publicKey, "Cross Fund", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
           .accounts({
             fundAccount,
-            fundTokenAccount,
-            toTokenAccount: recipientAccount,
-            whitelistEntry,
-            admin: newAdmin.publicKey,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            whitelistEntry: primaryEntry,
+            admin: user2.publicKey,
+            systemProgram: SystemProgram.programId,
           })
-          .signers([newAdmin])
+          .signers([user2])
           .rpc();
-
-        const recipientBalance = await getAccount(provider.connection, recipientAccount);
-        expect(Number(recipientBalance.amount)).to.equal(allocationAmount);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
       }
-    });
-
-    it("should fail when old admin tries to allocate funds", async () => {
-      const recipient = Keypair.generate();
-      const recipientAccount = await createAssociatedTokenAccount(
-        provider.connection,
-        admin,
-        mint,
-        recipient.publicKey
-      );
-      
-      // Find whitelist entry PDA
-      const [whitelistEntry] = PublicKey.findProgramAddressSync(
-        [Buffer.from("whitelist"), recipient.publicKey.toBuffer()],
-        program.programId
-      );
-      
-      // Add recipient to whitelist using current admin (newAdmin)
-      await program.methods
-        .addWhitelist(recipient.publicKey, "Old Admin Test Recipient")
-        .accounts({
-          fundAccount,
-          whitelistEntry,
-          admin: newAdmin.publicKey,
-          systemProgram: SystemProgram.programId,
-        })
-        .signers([newAdmin])
-        .rpc();
 
+      // A whitelist entry from one fund does not authorize allocations from another
       try {
         await program.methods
-          .allocateFunds(new anchor.BN(1_000_000))
+          .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
           .accounts({
             fundAccount,
+            mint,
             fundTokenAccount,
-            toTokenAccount: recipientAccount,
-            whitelistEntry,
-            admin: admin.publicKey,
+            toTokenAccount: recipientTokenAccount,
+            whitelistEntry: sameMintEntry,
+            authority: admin.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
           .signers([admin])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("UnauthorizedAdmin");
+        expect(error.message).to.include("ConstraintSeeds");
       }
-    });
 
-    it("should fail when non-admin tries to set new admin", async () => {
+      // Nor can one fund's admin deactivate another fund's recipient
       try {
         await program.methods
-          .setAdmin(user1.publicKey)
+          .removeWhitelist()
           .accounts({
             fundAccount,
-            currentAdmin: user1.publicKey,
+            whitelistEntry: sameMintEntry,
+            admin: admin.publicKey,
           })
-          .signers([user1])
+          .signers([admin])
           .rpc();
         expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("UnauthorizedAdmin");
+        expect(error.message).to.include("ConstraintSeeds");
       }
-    });
-  });
 
-  describe("Edge Cases", () => {
-    it("should handle zero amount deposits", async () => {
       try {
         await program.methods
-          .storeFunds(new anchor.BN(0))
+          .toggleWhitelist(false)
           .accounts({
             fundAccount,
-            fromTokenAccount: user1TokenAccount,
-            fundTokenAccount,
-            authority: user1.publicKey,
-            tokenProgram: TOKEN_PROGRAM_ID,
+            whitelistEntry: sameMintEntry,
+            admin: admin.publicKey,
           })
-          .signers([user1])
+          .signers([admin])
           .rpc();
+        expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("cannot transfer 0");
+        expect(error.message).to.include("ConstraintSeeds");
       }
+
+      const entryAfter = await program.account.whitelistEntry.fetch(sameMintEntry);
+      expect(entryAfter.isActive).to.be.true;
     });
+  });
 
-    it("should handle zero amount allocations", async () => {
-      const recipient = Keypair.generate();
-      const recipientAccount = await createAssociatedTokenAccount(
-        provider.connection,
-        admin,
-        mint,
-        recipient.publicKey
-      );
+  describe("Token-2022", () => {
+    const fund2022Id = newFundId();
+    let mint2022: PublicKey;
+    let fund2022: PublicKey;
+    let vault2022: PublicKey;
+    let user1Account2022: PublicKey;
+
+    before(async () => {
+      const connection = provider.connection;
+      mint2022 = await createMint(connection, admin, admin.publicKey, null, 9, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID);
+      [fund2022] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(fund2022Id)],
+        program.programId
+      );
+      vault2022 = await createAccount(connection, admin, mint2022, fund2022, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID);
+      user1Account2022 = await createAssociatedTokenAccount(connection, admin, mint2022, user1.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+      await mintTo(connection, admin, mint2022, user1Account2022, admin, INITIAL_MINT_AMOUNT, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+      await program.methods
+        .initialize(fund2022Id, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: fund2022,
+          mint: mint2022,
+          fundTokenAccount: vault2022,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should store and allocate Token-2022 funds", async () => {
+      await program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount: fund2022,
+          mint: mint2022,
+          fromTokenAccount: user1Account2022,
+          fundTokenAccount: vault2022,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint2022,
+        recipient.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID
+      );
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fund2022Id), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Token-2022 Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount: fund2022,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
+        .accounts({
+          fundAccount: fund2022,
+          mint: mint2022,
+          fundTokenAccount: vault2022,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+      const vault = await getAccount(provider.connection, vault2022, undefined, TOKEN_2022_PROGRAM_ID);
+      const recipientBalance = await getAccount(provider.connection, recipientAccount, undefined, TOKEN_2022_PROGRAM_ID);
+      const fundData = await program.account.fundAccount.fetch(fund2022);
+
+      expect(Number(vault.amount)).to.equal(DEPOSIT_AMOUNT - ALLOCATION_AMOUNT);
+      expect(Number(recipientBalance.amount)).to.equal(ALLOCATION_AMOUNT);
+      expect(fundData.totalFunds.toNumber()).to.equal(DEPOSIT_AMOUNT - ALLOCATION_AMOUNT);
+    });
+  });
+
+  describe("Fund Allocation", () => {
+    let recipientAccount: PublicKey;
+    let recipient: Keypair;
+    let whitelistEntry: PublicKey;
+
+    beforeEach(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipient.publicKey
+      );
       
       // Find whitelist entry PDA
-      const [whitelistEntry] = PublicKey.findProgramAddressSync(
-        [Buffer.from("whitelist"), recipient.publicKey.toBuffer()],
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
         program.programId
       );
       
       // Add recipient to whitelist
       await program.methods
-        .addWhitelist(recipient.publicKey, "Zero Amount Test Recipient")
+        .addWhitelist(recipient.publicKey, "Test Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
         .accounts({
           fundAccount,
           whitelistEntry,
-          admin: newAdmin.publicKey,
+          admin: admin.publicKey,
           systemProgram: SystemProgram.programId,
         })
-        .signers([newAdmin])
+        .signers([admin])
         .rpc();
+    });
+
+    it("should allow admin to allocate funds", async () => {
+      const initialFundBalance = await getAccount(provider.connection, fundTokenAccount);
+      const initialRecipientBalance = await getAccount(provider.connection, recipientAccount);
+      const initialFundAccount = await program.account.fundAccount.fetch(fundAccount);
+      const initialTotalFunds = initialFundAccount.totalFunds.toNumber();
+      const initialTotalAllocated = initialFundAccount.totalAllocated.toNumber();
+
+      const signature = await program.methods
+        .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const finalFundBalance = await getAccount(provider.connection, fundTokenAccount);
+      const finalRecipientBalance = await getAccount(provider.connection, recipientAccount);
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+
+      expect(Number(initialFundBalance.amount) - Number(finalFundBalance.amount)).to.equal(ALLOCATION_AMOUNT);
+      expect(Number(finalRecipientBalance.amount) - Number(initialRecipientBalance.amount)).to.equal(ALLOCATION_AMOUNT);
+      expect(fundAccountData.totalFunds.toNumber()).to.equal(initialTotalFunds - ALLOCATION_AMOUNT);
+      expect(fundAccountData.totalAllocated.toNumber()).to.equal(initialTotalAllocated + ALLOCATION_AMOUNT);
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.recipient.toString()).to.equal(recipient.publicKey.toString());
+      expect(event.data.vault.toString()).to.equal(fundTokenAccount.toString());
+      expect(event.data.amount.toNumber()).to.equal(ALLOCATION_AMOUNT);
+      expect(event.data.newTotal.toNumber()).to.equal(initialTotalFunds - ALLOCATION_AMOUNT);
+      expect(event.data.totalAllocated.toNumber()).to.equal(initialTotalAllocated + ALLOCATION_AMOUNT);
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const [returnData] = tx.meta.returnData.data;
+      const returnedTotal = Buffer.from(returnData, "base64").readBigUInt64LE(0);
+      expect(Number(returnedTotal)).to.equal(initialTotalFunds - ALLOCATION_AMOUNT);
+    });
 
+    it("should fail when non-admin tries to allocate funds", async () => {
       try {
         await program.methods
-          .allocateFunds(new anchor.BN(0))
+          .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
           .accounts({
             fundAccount,
+            mint,
             fundTokenAccount,
             toTokenAccount: recipientAccount,
             whitelistEntry,
-            admin: newAdmin.publicKey,
+            authority: user1.publicKey,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
-          .signers([newAdmin])
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAllocator");
+      }
+    });
+
+    it("should fail when allocating more than available funds", async () => {
+      const currentFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+      const excessiveAmount = currentFunds + 1;
+
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(excessiveAmount))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
           .rpc();
+        expect.fail("Should have thrown an error");
       } catch (error) {
-        expect(error.message).to.include("cannot transfer 0");
+        expect(error.message).to.include("InsufficientFunds");
       }
     });
   });
-});
+
+  describe("Allocation Caps", () => {
+    const CAP = 20_000_000; // 20 tokens
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipient.publicKey
+      );
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Capped Recipient", new anchor.BN(CAP), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    it("should allow allocations up to the cap", async () => {
+      await allocate(CAP);
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.totalAllocated.toNumber()).to.equal(CAP);
+    });
+
+    it("should reject allocations beyond the cap", async () => {
+      try {
+        await allocate(1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationCapExceeded");
+      }
+    });
+
+    it("should honor a cap raised mid-stream", async () => {
+      await program.methods
+        .updateAllocationCap(new anchor.BN(CAP * 2))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await allocate(CAP);
+
+      try {
+        await allocate(1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationCapExceeded");
+      }
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.totalAllocated.toNumber()).to.equal(CAP * 2);
+    });
+
+    it("should reject cap updates from non-admins", async () => {
+      try {
+        await program.methods
+          .updateAllocationCap(new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Vault Binding", () => {
+    let foreignTokenAccount: PublicKey;
+
+    before(async () => {
+      // Token account of the right mint owned by someone other than the fund
+      foreignTokenAccount = await createAccount(provider.connection, admin, mint, user2.publicKey, Keypair.generate());
+    });
+
+    it("should reject deposits into a foreign token account", async () => {
+      try {
+        await program.methods
+          .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+          .accounts({
+            fundAccount,
+            mint,
+            fromTokenAccount: user1TokenAccount,
+            fundTokenAccount: foreignTokenAccount,
+            authority: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("VaultMismatch");
+      }
+    });
+
+    it("should reject allocations from a foreign token account", async () => {
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Vault Binding Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(ALLOCATION_AMOUNT))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount: foreignTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("VaultMismatch");
+      }
+    });
+  });
+
+  describe("Fund Account Validation", () => {
+    it("should reject a program-owned account that is not the fund PDA", async () => {
+      const recipient = Keypair.generate();
+      const [spoofedFundAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Spoof Source", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry: spoofedFundAccount,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await program.methods
+          .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+          .accounts({
+            fundAccount: spoofedFundAccount,
+            mint,
+            fromTokenAccount: user1TokenAccount,
+            fundTokenAccount,
+            authority: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.match(/AccountDiscriminatorMismatch|ConstraintSeeds/);
+      }
+    });
+  });
+
+  describe("Batch Allocation", () => {
+    const BATCH_AMOUNT = 1_000_000; // 1 token
+    const recipients: { owner: Keypair; tokenAccount: PublicKey; whitelistEntry: PublicKey }[] = [];
+
+    before(async () => {
+      for (let i = 0; i < 3; i++) {
+        const owner = Keypair.generate();
+        const tokenAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, owner.publicKey);
+        const [whitelistEntry] = PublicKey.findProgramAddressSync(
+          [Buffer.from("whitelist"), Buffer.from(fundId), owner.publicKey.toBuffer()],
+          program.programId
+        );
+
+        await program.methods
+          .addWhitelist(owner.publicKey, `Batch Recipient ${i}`, new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+
+        recipients.push({ owner, tokenAccount, whitelistEntry });
+      }
+    });
+
+    const remainingAccountsFor = (batch: typeof recipients) =>
+      batch.flatMap(({ whitelistEntry, tokenAccount }) => [
+        { pubkey: whitelistEntry, isSigner: false, isWritable: true },
+        { pubkey: tokenAccount, isSigner: false, isWritable: true },
+      ]);
+
+    const allocateBatch = (amounts: number[], batch: typeof recipients) =>
+      program.methods
+        .allocateFundsBatch(amounts.map((amount) => new anchor.BN(amount)))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(remainingAccountsFor(batch))
+        .signers([admin])
+        .rpc();
+
+    it("should pay every recipient in one transaction", async () => {
+      const initialTotalFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+
+      await allocateBatch(recipients.map(() => BATCH_AMOUNT), recipients);
+
+      for (const { tokenAccount } of recipients) {
+        const balance = await getAccount(provider.connection, tokenAccount);
+        expect(Number(balance.amount)).to.equal(BATCH_AMOUNT);
+      }
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAccountData.totalFunds.toNumber()).to.equal(initialTotalFunds - BATCH_AMOUNT * recipients.length);
+    });
+
+    it("should pay nobody when one recipient fails its checks", async () => {
+      const outsider = Keypair.generate();
+      const outsiderAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, outsider.publicKey);
+      // Whitelist entry of another recipient paired with the outsider's token account
+      const batch = [recipients[0], { owner: outsider, tokenAccount: outsiderAccount, whitelistEntry: recipients[1].whitelistEntry }];
+
+      try {
+        await allocateBatch([BATCH_AMOUNT, BATCH_AMOUNT], batch);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistAddressMismatch");
+      }
+
+      const balance = await getAccount(provider.connection, recipients[0].tokenAccount);
+      expect(Number(balance.amount)).to.equal(BATCH_AMOUNT);
+    });
+
+    it("should reject batches over the size limit", async () => {
+      const oversized = Array(11).fill(recipients[0]);
+
+      try {
+        await allocateBatch(oversized.map(() => 1), oversized);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.match(/BatchTooLarge|too large/);
+      }
+    });
+  });
+
+  describe("Fund Withdrawal", () => {
+    const WITHDRAWAL_AMOUNT = 5_000_000; // 5 tokens
+
+    it("should allow admin to withdraw funds", async () => {
+      const initialFundBalance = await getAccount(provider.connection, fundTokenAccount);
+      const initialDestinationBalance = await getAccount(provider.connection, user1TokenAccount);
+      const initialTotalFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+
+      await program.methods
+        .withdrawFunds(new anchor.BN(WITHDRAWAL_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          destinationTokenAccount: user1TokenAccount,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+      const finalFundBalance = await getAccount(provider.connection, fundTokenAccount);
+      const finalDestinationBalance = await getAccount(provider.connection, user1TokenAccount);
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+
+      expect(Number(initialFundBalance.amount) - Number(finalFundBalance.amount)).to.equal(WITHDRAWAL_AMOUNT);
+      expect(Number(finalDestinationBalance.amount) - Number(initialDestinationBalance.amount)).to.equal(WITHDRAWAL_AMOUNT);
+      expect(fundAccountData.totalFunds.toNumber()).to.equal(initialTotalFunds - WITHDRAWAL_AMOUNT);
+    });
+
+    it("should fail when non-admin tries to withdraw funds", async () => {
+      try {
+        await program.methods
+          .withdrawFunds(new anchor.BN(WITHDRAWAL_AMOUNT))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            destinationTokenAccount: user1TokenAccount,
+            admin: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should fail when withdrawing more than available funds", async () => {
+      const currentFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+
+      try {
+        await program.methods
+          .withdrawFunds(new anchor.BN(currentFunds + 1))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            destinationTokenAccount: user1TokenAccount,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientFunds");
+      }
+    });
+  });
+
+  describe("Pause", () => {
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setPaused = (paused: boolean) =>
+      program.methods
+        .setPaused(paused)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const allocate = () =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    const deposit = () =>
+      program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount: user1TokenAccount,
+          fundTokenAccount,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Pause Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should fail when non-admin tries to pause", async () => {
+      try {
+        await program.methods
+          .setPaused(true)
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should block allocations while paused", async () => {
+      await setPaused(true);
+
+      try {
+        await allocate();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundPaused");
+      }
+    });
+
+    it("should block deposits while paused", async () => {
+      try {
+        await deposit();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundPaused");
+      }
+    });
+
+    it("should allow deposits and allocations again after unpausing", async () => {
+      const signature = await program.methods
+        .setPaused(false)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "FundPausedEvent");
+      expect(event.data.paused).to.equal(false);
+      expect(event.data.actor.toString()).to.equal(admin.publicKey.toString());
+
+      await deposit();
+      await allocate();
+    });
+  });
+
+  describe("Whitelist Labels", () => {
+    let recipient: Keypair;
+    let whitelistEntry: PublicKey;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Old Vendor Name", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    const updateLabel = (label: string, signer: Keypair = admin) =>
+      program.methods
+        .updateWhitelistLabel(label)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    it("should rename an entry without touching its other fields", async () => {
+      const before = await program.account.whitelistEntry.fetch(whitelistEntry);
+
+      const signature = await updateLabel("New Vendor Name");
+
+      const after = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(after.label).to.equal("New Vendor Name");
+      expect(after.addedAt.toNumber()).to.equal(before.addedAt.toNumber());
+      expect(after.addedBy.toString()).to.equal(before.addedBy.toString());
+      expect(after.isActive).to.equal(before.isActive);
+
+      const event = await findEvent(signature, "WhitelistLabelUpdated");
+      expect(event.data.address.toString()).to.equal(recipient.publicKey.toString());
+      expect(event.data.oldLabel).to.equal("Old Vendor Name");
+      expect(event.data.newLabel).to.equal("New Vendor Name");
+    });
+
+    it("should rename inactive entries too", async () => {
+      await program.methods
+        .removeWhitelist()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await updateLabel("Archived Vendor");
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.isActive).to.equal(false);
+      expect(entry.label).to.equal("Archived Vendor");
+    });
+
+    it("should reject labels over 64 characters", async () => {
+      try {
+        await updateLabel("x".repeat(65));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("LabelTooLong");
+      }
+    });
+
+    it("should reject non-admin signers", async () => {
+      try {
+        await updateLabel("Hijacked", user1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Whitelist Reactivation", () => {
+    it("should allow remove -> re-add -> allocate for the same address", async () => {
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Returning Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .removeWhitelist()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .reactivateWhitelist("Returning Recipient (rehired)")
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.isActive).to.equal(true);
+      expect(entry.label).to.equal("Returning Recipient (rehired)");
+
+      await program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+      const balance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(balance.amount)).to.equal(1_000_000);
+    });
+  });
+
+  describe("Unlock Times", () => {
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const farFuture = Math.floor(Date.now() / 1000) + 365 * 24 * 60 * 60;
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Vesting Recipient", new anchor.BN(0), new anchor.BN(farFuture), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    const allocate = () =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    it("should reject allocations before the unlock time", async () => {
+      try {
+        await allocate();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationLocked");
+      }
+    });
+
+    it("should allow allocations once the admin clears the unlock time", async () => {
+      await program.methods
+        .setUnlockTime(new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await allocate();
+    });
+  });
+
+  describe("Allocation Cooldown", () => {
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setCooldown = (secs: number) =>
+      program.methods
+        .setAllocationCooldown(new anchor.BN(secs))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const allocate = () =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Cooldown Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    after(async () => {
+      await setCooldown(0);
+    });
+
+    it("should reject a second allocation inside the cooldown window", async () => {
+      await setCooldown(3600);
+      await allocate();
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.lastAllocatedAt.toNumber()).to.be.greaterThan(0);
+
+      try {
+        await allocate();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationCooldownActive");
+      }
+    });
+
+    it("should allow allocations again once the cooldown has elapsed", async () => {
+      await setCooldown(1);
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      await allocate();
+    });
+
+    it("should fail to set a negative cooldown", async () => {
+      try {
+        await setCooldown(-1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidCooldown");
+      }
+    });
+
+    it("should fail to set the cooldown when not admin", async () => {
+      try {
+        await program.methods
+          .setAllocationCooldown(new anchor.BN(60))
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Allocation Fees", () => {
+    const FEE_BPS = 500; // 5%
+    const ALLOCATION = 1_000_000;
+    const treasury = Keypair.generate();
+    let treasuryAccount: PublicKey;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setFee = (feeBps: number, destination: PublicKey) =>
+      program.methods
+        .setFee(feeBps, destination)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      treasuryAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, treasury.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Fee Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setFee(FEE_BPS, treasury.publicKey);
+    });
+
+    after(async () => {
+      await setFee(0, PublicKey.default);
+    });
+
+    it("should skim the fee to the fee destination", async () => {
+      const fundBefore = await program.account.fundAccount.fetch(fundAccount);
+
+      const signature = await program.methods
+        .allocateFunds(new anchor.BN(ALLOCATION))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          feeTokenAccount: treasuryAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const expectedFee = (ALLOCATION * FEE_BPS) / 10_000;
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      const treasuryBalance = await getAccount(provider.connection, treasuryAccount);
+      expect(Number(recipientBalance.amount)).to.equal(ALLOCATION - expectedFee);
+      expect(Number(treasuryBalance.amount)).to.equal(expectedFee);
+
+      const fundAfter = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAfter.totalFunds.toNumber()).to.equal(fundBefore.totalFunds.toNumber() - ALLOCATION);
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.amount.toNumber()).to.equal(ALLOCATION);
+      expect(event.data.fee.toNumber()).to.equal(expectedFee);
+    });
+
+    it("should fail without a fee account while a fee is set", async () => {
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(ALLOCATION))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            feeTokenAccount: null,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FeeAccountRequired");
+      }
+    });
+
+    it("should fail with a fee account not owned by the fee destination", async () => {
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(ALLOCATION))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            feeTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidFeeDestination");
+      }
+    });
+
+    it("should reject a fee above 10000 bps", async () => {
+      try {
+        await setFee(10_001, treasury.publicKey);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidFeeBps");
+      }
+    });
+
+    it("should fail to set the fee when not admin", async () => {
+      try {
+        await program.methods
+          .setFee(100, user1.publicKey)
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Daily Limit", () => {
+    const HEADROOM = 2_000_000;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setDailyLimit = (limit: number) =>
+      program.methods
+        .setDailyLimit(new anchor.BN(limit))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Daily Limit Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    after(async () => {
+      await setDailyLimit(0);
+    });
+
+    it("should emit an event when the limit changes", async () => {
+      // Earlier suites already allocated within the current window
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const limit = fund.allocatedInWindow.toNumber() + HEADROOM;
+
+      const signature = await setDailyLimit(limit);
+
+      const event = await findEvent(signature, "DailyLimitUpdated");
+      expect(event.data.oldLimit.toNumber()).to.equal(0);
+      expect(event.data.newLimit.toNumber()).to.equal(limit);
+      expect(event.data.actor.toString()).to.equal(admin.publicKey.toString());
+    });
+
+    it("should allow an allocation that exactly hits the limit", async () => {
+      await allocate(HEADROOM);
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.allocatedInWindow.toNumber()).to.equal(fund.dailyLimit.toNumber());
+    });
+
+    it("should reject allocations beyond the limit", async () => {
+      try {
+        await allocate(1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DailyLimitExceeded");
+      }
+    });
+
+    it("should fail to set the limit when not admin", async () => {
+      try {
+        await program.methods
+          .setDailyLimit(new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Operators", () => {
+    const operator = Keypair.generate();
+    let operatorAccount: PublicKey;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocateAs = (signer: Keypair) =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          operator: operatorAccount,
+          authority: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      [operatorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("operator"), fundAccount.toBuffer(), operator.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Operator Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should fail to add an operator when not admin", async () => {
+      try {
+        await program.methods
+          .addOperator(operator.publicKey)
+          .accounts({
+            fundAccount,
+            operatorAccount,
+            admin: user1.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should let an active operator allocate", async () => {
+      const signature = await program.methods
+        .addOperator(operator.publicKey)
+        .accounts({
+          fundAccount,
+          operatorAccount,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "OperatorAdded");
+      expect(event.data.operator.toString()).to.equal(operator.publicKey.toString());
+      expect(event.data.actor.toString()).to.equal(admin.publicKey.toString());
+
+      const record = await program.account.operator.fetch(operatorAccount);
+      expect(record.operator.toString()).to.equal(operator.publicKey.toString());
+      expect(record.isActive).to.be.true;
+
+      await allocateAs(operator);
+
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(recipientBalance.amount)).to.equal(1_000_000);
+    });
+
+    it("should not let an operator change admin settings", async () => {
+      try {
+        await program.methods
+          .setPaused(true)
+          .accounts({
+            fundAccount,
+            admin: operator.publicKey,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should not let an operator manage the whitelist", async () => {
+      const outsider = Keypair.generate();
+      const [outsiderEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), outsider.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .addWhitelist(outsider.publicKey, "Operator Added", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry: outsiderEntry,
+            admin: operator.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+
+      try {
+        await program.methods
+          .toggleWhitelist(false)
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: operator.publicKey,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should reject allocations from a removed operator", async () => {
+      await program.methods
+        .removeOperator()
+        .accounts({
+          fundAccount,
+          operatorAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await allocateAs(operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAllocator");
+      }
+    });
+  });
+
+  describe("Allocation Approvals", () => {
+    const THRESHOLD = 5_000_000;
+    const approver = Keypair.generate();
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setApprovalConfig = (approverKey: PublicKey, threshold: number, ttl: number) =>
+      program.methods
+        .setApprovalConfig(approverKey, new anchor.BN(threshold), new anchor.BN(ttl))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const propose = async (amount: number) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const [proposal] = PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), fundAccount.toBuffer(), fund.proposalNonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .proposeAllocation(new anchor.BN(amount), recipient.publicKey)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          proposal,
+          authority: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      return proposal;
+    };
+
+    const approve = (proposal: PublicKey, signer: Keypair) =>
+      program.methods
+        .approveAllocation()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          proposal,
+          proposer: admin.publicKey,
+          approver: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Large Payout Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setApprovalConfig(approver.publicKey, THRESHOLD, 3600);
+    });
+
+    after(async () => {
+      await setApprovalConfig(PublicKey.default, 0, 0);
+    });
+
+    it("should reject the admin as approver", async () => {
+      try {
+        await setApprovalConfig(admin.publicKey, THRESHOLD, 3600);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidApprover");
+      }
+    });
+
+    it("should force large allocations through a proposal", async () => {
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(THRESHOLD))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationRequiresApproval");
+      }
+    });
+
+    it("should reject approval from anyone but the approver", async () => {
+      const proposal = await propose(THRESHOLD);
+
+      try {
+        await approve(proposal, user1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedApprover");
+      }
+
+      await program.methods
+        .cancelAllocation()
+        .accounts({
+          fundAccount,
+          proposal,
+          proposer: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const closed = await provider.connection.getAccountInfo(proposal);
+      expect(closed).to.be.null;
+    });
+
+    it("should execute the transfer once approved", async () => {
+      const proposal = await propose(THRESHOLD);
+
+      await approve(proposal, approver);
+
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(recipientBalance.amount)).to.equal(THRESHOLD);
+
+      const closed = await provider.connection.getAccountInfo(proposal);
+      expect(closed).to.be.null;
+    });
+  });
+
+  describe("Minimum Allocation", () => {
+    const MIN_ALLOCATION = 1_000_000;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setMinAllocation = (min: number) =>
+      program.methods
+        .setMinAllocation(new anchor.BN(min))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Dust Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setMinAllocation(MIN_ALLOCATION);
+    });
+
+    after(async () => {
+      await setMinAllocation(0);
+    });
+
+    it("should reject allocations below the minimum", async () => {
+      try {
+        await allocate(MIN_ALLOCATION - 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("BelowMinAllocation");
+      }
+    });
+
+    it("should allow allocations at the minimum", async () => {
+      await allocate(MIN_ALLOCATION);
+
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(recipientBalance.amount)).to.equal(MIN_ALLOCATION);
+    });
+
+    it("should fail to set the minimum when not admin", async () => {
+      try {
+        await program.methods
+          .setMinAllocation(new anchor.BN(1))
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Minimum Deposit", () => {
+    const MIN_DEPOSIT = 1_000;
+
+    const setMinDeposit = (min: number) =>
+      program.methods
+        .setMinDeposit(new anchor.BN(min))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const deposit = (amount: number) =>
+      program.methods
+        .storeFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount: user1TokenAccount,
+          fundTokenAccount,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+    before(async () => {
+      await setMinDeposit(MIN_DEPOSIT);
+    });
+
+    after(async () => {
+      await setMinDeposit(0);
+    });
+
+    it("should reject deposits below the minimum", async () => {
+      try {
+        await deposit(MIN_DEPOSIT - 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositTooSmall");
+      }
+    });
+
+    it("should accept a deposit of exactly the minimum", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      await deposit(MIN_DEPOSIT);
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + MIN_DEPOSIT);
+    });
+
+    it("should reject a zero deposit as ZeroAmount", async () => {
+      try {
+        await deposit(0);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ZeroAmount");
+      }
+    });
+
+    it("should fail to set the minimum when not admin", async () => {
+      try {
+        await program.methods
+          .setMinDeposit(new anchor.BN(1))
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Multi-Admin", () => {
+    const coAdmin = Keypair.generate();
+    const AMOUNT = 1_000_000;
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const createOperation = async (action: any) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const [operation] = PublicKey.findProgramAddressSync(
+        [Buffer.from("admin_op"), fundAccount.toBuffer(), fund.adminOpNonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .createAdminOperation(action)
+        .accounts({
+          fundAccount,
+          operation,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      return operation;
+    };
+
+    const approveOperation = (operation: PublicKey, signer: Keypair) =>
+      program.methods
+        .approveAdminOperation()
+        .accounts({
+          fundAccount,
+          operation,
+          admin: signer.publicKey,
+        })
+        .signers([signer])
+        .rpc();
+
+    const executeAllocation = (operation: PublicKey) =>
+      program.methods
+        .executeAllocationOperation()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          operation,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Multi-Admin Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should let a single admin add a co-admin and raise the threshold", async () => {
+      await program.methods
+        .addAdmin(coAdmin.publicKey)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .setAdminThreshold(2)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.coAdmins.map((key) => key.toString())).to.include(coAdmin.publicKey.toString());
+      expect(fund.adminThreshold).to.equal(2);
+    });
+
+    it("should reject direct allocations above threshold 1", async () => {
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(AMOUNT))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+    });
+
+    it("should execute an allocation once enough admins approve", async () => {
+      const operation = await createOperation({
+        allocate: { recipient: recipient.publicKey, amount: new anchor.BN(AMOUNT) },
+      });
+
+      try {
+        await approveOperation(operation, admin);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AdminOperationAlreadyApproved");
+      }
+
+      try {
+        await executeAllocation(operation);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientApprovals");
+      }
+
+      await approveOperation(operation, coAdmin);
+      await executeAllocation(operation);
+
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(recipientBalance.amount)).to.equal(AMOUNT);
+    });
+
+    it("should reject a single-admin withdrawal above threshold 1", async () => {
+      try {
+        await program.methods
+          .withdrawFunds(new anchor.BN(AMOUNT))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            destinationTokenAccount: recipientAccount,
+            admin: coAdmin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([coAdmin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+    });
+
+    it("should reject a single-admin fee change above threshold 1", async () => {
+      try {
+        await program.methods
+          .setFee(10_000, coAdmin.publicKey)
+          .accounts({
+            fundAccount,
+            admin: coAdmin.publicKey,
+          })
+          .signers([coAdmin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.feeBps).to.equal(0);
+    });
+
+    it("should change fund settings through an admin operation", async () => {
+      const operation = await createOperation({ setPaused: { paused: true } });
+      await approveOperation(operation, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      let fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.paused).to.be.true;
+
+      const resume = await createOperation({ setPaused: { paused: false } });
+      await approveOperation(resume, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation: resume,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.paused).to.be.false;
+    });
+
+    it("should reject single-admin deposit settings above threshold 1", async () => {
+      try {
+        await program.methods
+          .setDepositsLocked(true)
+          .accounts({
+            fundAccount,
+            admin: coAdmin.publicKey,
+          })
+          .signers([coAdmin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+
+      try {
+        await program.methods
+          .setMinDeposit(new anchor.BN(AMOUNT))
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.depositsLocked).to.be.false;
+      expect(fund.minDeposit.toNumber()).to.equal(0);
+    });
+
+    it("should change the minimum deposit through an admin operation", async () => {
+      const operation = await createOperation({ setMinDeposit: { minDeposit: new anchor.BN(AMOUNT) } });
+      await approveOperation(operation, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      let fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.minDeposit.toNumber()).to.equal(AMOUNT);
+
+      const reset = await createOperation({ setMinDeposit: { minDeposit: new anchor.BN(0) } });
+      await approveOperation(reset, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation: reset,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.minDeposit.toNumber()).to.equal(0);
+    });
+
+    it("should update an allocation cap through an admin operation", async () => {
+      const before = await program.account.whitelistEntry.fetch(whitelistEntry);
+
+      try {
+        await program.methods
+          .updateAllocationCap(new anchor.BN(AMOUNT))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+
+      const capOperation = async (maxAllocation: anchor.BN) => {
+        const operation = await createOperation({
+          updateAllocationCap: { address: recipient.publicKey, maxAllocation },
+        });
+        await approveOperation(operation, coAdmin);
+        return operation;
+      };
+
+      // The entry the action names has to be supplied
+      const missing = await capOperation(new anchor.BN(AMOUNT));
+      try {
+        await program.methods
+          .executeAdminOperation()
+          .accounts({
+            fundAccount,
+            operation: missing,
+            proposer: admin.publicKey,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistEntryRequired");
+      }
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation: missing,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+          whitelistEntry,
+        })
+        .signers([admin])
+        .rpc();
+
+      let entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.maxAllocation.toNumber()).to.equal(AMOUNT);
+
+      const restore = await capOperation(before.maxAllocation);
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation: restore,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+          whitelistEntry,
+        })
+        .signers([admin])
+        .rpc();
+
+      entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.maxAllocation.toString()).to.equal(before.maxAllocation.toString());
+    });
+
+    it("should change the audit config only through an admin operation", async () => {
+      try {
+        await program.methods
+          .setAuditConfig(true, new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+            auditRecord: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ThresholdApprovalRequired");
+      }
+
+      const operation = await createOperation({ setAuditConfig: { enabled: false, retentionSecs: new anchor.BN(3_600) } });
+      await approveOperation(operation, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.auditEnabled).to.be.false;
+      expect(fund.auditRetentionSecs.toNumber()).to.equal(3_600);
+    });
+
+    it("should lower the threshold through an admin operation", async () => {
+      const operation = await createOperation({ setThreshold: { threshold: 1 } });
+      await approveOperation(operation, coAdmin);
+
+      await program.methods
+        .executeAdminOperation()
+        .accounts({
+          fundAccount,
+          operation,
+          proposer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .removeAdmin(coAdmin.publicKey)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.adminThreshold).to.equal(1);
+      expect(fund.coAdmins).to.be.empty;
+    });
+  });
+
+  describe("Allocation Log", () => {
+    let allocationLog: PublicKey;
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Logged Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should record each allocation at the head of the log", async () => {
+      const before = await program.account.allocationLog.fetch(allocationLog);
+
+      await program.methods
+        .allocateFunds(new anchor.BN(1_234_567))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          allocationLog,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+      const log = await program.account.allocationLog.fetch(allocationLog);
+      expect(log.fund.toString()).to.equal(fundAccount.toString());
+      expect(log.head).to.equal((before.head + 1) % log.entries.length);
+
+      const record = log.entries[before.head];
+      expect(record.recipient.toString()).to.equal(recipient.publicKey.toString());
+      expect(record.amount.toNumber()).to.equal(1_234_567);
+      expect(record.timestamp.toNumber()).to.be.greaterThan(0);
+    });
+  });
+
+  describe("Whitelist Expiration", () => {
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocate = () =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Expires almost immediately
+      const expiresAt = Math.floor(Date.now() / 1000) + 1;
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Expiring Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(expiresAt))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject allocations once the entry has expired", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      try {
+        await allocate();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistExpired");
+      }
+    });
+
+    it("should reject renewing to a time in the past", async () => {
+      try {
+        await program.methods
+          .renewWhitelist(new anchor.BN(1))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidExpiry");
+      }
+    });
+
+    it("should allow allocations again after renewal", async () => {
+      const nextYear = Math.floor(Date.now() / 1000) + 365 * 24 * 60 * 60;
+      await program.methods
+        .renewWhitelist(new anchor.BN(nextYear))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await allocate();
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.expiresAt.toNumber()).to.equal(nextYear);
+    });
+
+    it("should let anyone deactivate an entry once it expires", async () => {
+      const vendor = Keypair.generate();
+      const [vendorEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), vendor.publicKey.toBuffer()],
+        program.programId
+      );
+      const expiresAt = Math.floor(Date.now() / 1000) + 2;
+
+      await program.methods
+        .addWhitelist(vendor.publicKey, "Cranked Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(expiresAt))
+        .accounts({
+          fundAccount,
+          whitelistEntry: vendorEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const expire = () =>
+        program.methods
+          .expireWhitelist()
+          .accounts({
+            fundAccount,
+            whitelistEntry: vendorEntry,
+            caller: provider.wallet.publicKey,
+          })
+          .rpc();
+
+      try {
+        await expire();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistNotExpired");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      const fundBefore = await program.account.fundAccount.fetch(fundAccount);
+
+      await expire();
+
+      const entry = await program.account.whitelistEntry.fetch(vendorEntry);
+      expect(entry.isActive).to.be.false;
+      const fundAfter = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAfter.whitelistActive).to.equal(fundBefore.whitelistActive - 1);
+    });
+  });
+
+  describe("Batch Whitelist", () => {
+    const recipients = Array.from({ length: 10 }, () => Keypair.generate());
+    const entryFor = (key: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("whitelist"), Buffer.from(fundId), key.toBuffer()], program.programId)[0];
+
+    const addBatch = (keys: PublicKey[]) =>
+      program.methods
+        .addWhitelistBatch(keys.map((address, i) => ({ address, label: `Cohort Grantee ${i}` })))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(keys.map((key) => ({ pubkey: entryFor(key), isWritable: true, isSigner: false })))
+        .signers([admin])
+        .rpc();
+
+    it("should add 10 entries in one transaction", async () => {
+      const fundBefore = await program.account.fundAccount.fetch(fundAccount);
+
+      await addBatch(recipients.map((recipient) => recipient.publicKey));
+
+      const fundAfter = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAfter.whitelistActive).to.equal(fundBefore.whitelistActive + recipients.length);
+      expect(fundAfter.whitelistTotalCreated).to.equal(fundBefore.whitelistTotalCreated + recipients.length);
+
+      const entry = await program.account.whitelistEntry.fetch(entryFor(recipients[3].publicKey));
+      expect(entry.label).to.equal("Cohort Grantee 3");
+      expect(entry.isActive).to.be.true;
+    });
+
+    it("should allocate to an entry added in a batch", async () => {
+      const recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipients[0].publicKey
+      );
+
+      await program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry: entryFor(recipients[0].publicKey),
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+      const recipientBalance = await getAccount(provider.connection, recipientAccount);
+      expect(Number(recipientBalance.amount)).to.equal(1_000_000);
+    });
+
+    it("should reject duplicate addresses in a batch", async () => {
+      const duplicate = Keypair.generate().publicKey;
+
+      try {
+        await addBatch([duplicate, duplicate]);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DuplicateBatchAddress");
+      }
+    });
+
+    it("should add an entry whose address was prefunded by someone else", async () => {
+      const target = Keypair.generate().publicKey;
+
+      // A stray lamport on the predictable PDA must not block onboarding
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.transfer({ fromPubkey: user1.publicKey, toPubkey: entryFor(target), lamports: 1 })
+        ),
+        [user1]
+      );
+
+      await addBatch([target]);
+
+      const entry = await program.account.whitelistEntry.fetch(entryFor(target));
+      expect(entry.address.toString()).to.equal(target.toString());
+      expect(entry.isActive).to.be.true;
+    });
+  });
+
+  describe("Whitelist Tiers", () => {
+    const TIER_LIMITS = [1_000_000, 2_000_000, 3_000_000, 4_000_000];
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const setTierLimits = (limits: number[]) =>
+      program.methods
+        .setTierLimits(limits.map((limit) => new anchor.BN(limit)))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const setTier = (tier: number) =>
+      program.methods
+        .setWhitelistTier(tier)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Tiered Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setTierLimits(TIER_LIMITS);
+    });
+
+    after(async () => {
+      await setTierLimits([0, 0, 0, 0]);
+    });
+
+    TIER_LIMITS.forEach((limit, tier) => {
+      it(`should enforce the tier ${tier} limit`, async () => {
+        const signature = await setTier(tier);
+        const event = await findEvent(signature, "WhitelistTierUpdated");
+        expect(event.data.newTier).to.equal(tier);
+
+        try {
+          await allocate(limit + 1);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("TierLimitExceeded");
+        }
+
+        await allocate(limit);
+      });
+    });
+
+    it("should reject an out-of-range tier", async () => {
+      try {
+        await setTier(TIER_LIMITS.length);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidTier");
+      }
+    });
+  });
+
+  describe("Pro-Rata Distribution", () => {
+    const WEIGHTS = [1, 1, 2];
+    const recipients: { tokenAccount: PublicKey; whitelistEntry: PublicKey }[] = [];
+
+    const setWeight = (whitelistEntry: PublicKey, weight: number) =>
+      program.methods
+        .setWhitelistWeight(weight)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const distribute = (totalAmount: number, batch: typeof recipients) =>
+      program.methods
+        .distributeProRata(new anchor.BN(totalAmount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          batch.flatMap(({ whitelistEntry, tokenAccount }) => [
+            { pubkey: whitelistEntry, isSigner: false, isWritable: true },
+            { pubkey: tokenAccount, isSigner: false, isWritable: true },
+          ])
+        )
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      for (const [i, weight] of WEIGHTS.entries()) {
+        const owner = Keypair.generate();
+        const tokenAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, owner.publicKey);
+        const [whitelistEntry] = PublicKey.findProgramAddressSync(
+          [Buffer.from("whitelist"), Buffer.from(fundId), owner.publicKey.toBuffer()],
+          program.programId
+        );
+
+        await program.methods
+          .addWhitelist(owner.publicKey, `Pro-Rata Recipient ${i}`, new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        await setWeight(whitelistEntry, weight);
+
+        recipients.push({ tokenAccount, whitelistEntry });
+      }
+    });
+
+    it("should split the pool by weight and send the dust to the first recipient", async () => {
+      // 1_000_001 / 4 leaves one unit of dust
+      await distribute(1_000_001, recipients);
+
+      const balances = await Promise.all(
+        recipients.map(async ({ tokenAccount }) => Number((await getAccount(provider.connection, tokenAccount)).amount))
+      );
+      expect(balances).to.deep.equal([250_001, 250_000, 500_000]);
+    });
+
+    it("should fail when the total weight is zero", async () => {
+      await setWeight(recipients[0].whitelistEntry, 0);
+
+      try {
+        await distribute(1_000_000, recipients.slice(0, 1));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ZeroTotalWeight");
+      }
+    });
+  });
+
+  describe("Reconciliation", () => {
+    const STRAY_AMOUNT = 500_000;
+
+    it("should sync total_funds with the vault after a direct transfer", async () => {
+      // Bypasses store_funds, so total_funds does not see it
+      await transfer(provider.connection, user1, user1TokenAccount, fundTokenAccount, user1, STRAY_AMOUNT);
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      const signature = await program.methods
+        .reconcile()
+        .accounts({
+          fundAccount,
+          fundTokenAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const vault = await getAccount(provider.connection, fundTokenAccount);
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(Number(vault.amount));
+
+      const event = await findEvent(signature, "FundsReconciled");
+      expect(event.data.oldTotal.toNumber()).to.equal(before.totalFunds.toNumber());
+      expect(event.data.newTotal.toNumber()).to.equal(Number(vault.amount));
+      expect(event.data.surplus.toNumber()).to.equal(Number(vault.amount) - before.totalFunds.toNumber());
+    });
+
+    it("should correct downward and flag a shortfall when the vault holds less", async () => {
+      // A transfer-fee mint withholds part of each deposit, so the vault
+      // ends up below what store_funds recorded
+      const connection = provider.connection;
+      const FEE_BPS = 100;
+      const feeMint = Keypair.generate();
+      const feeFundId = newFundId();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: admin.publicKey,
+            newAccountPubkey: feeMint.publicKey,
+            space: mintLen,
+            lamports: await connection.getMinimumBalanceForRentExemption(mintLen),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            feeMint.publicKey,
+            admin.publicKey,
+            admin.publicKey,
+            FEE_BPS,
+            BigInt(DEPOSIT_AMOUNT),
+            TOKEN_2022_PROGRAM_ID
+          ),
+          createInitializeMintInstruction(feeMint.publicKey, 6, admin.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [admin, feeMint]
+      );
+
+      const [feeFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(feeFundId)],
+        program.programId
+      );
+      const feeVault = await createAccount(connection, admin, feeMint.publicKey, feeFund, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID);
+      const source = await createAssociatedTokenAccount(connection, admin, feeMint.publicKey, user1.publicKey, undefined, TOKEN_2022_PROGRAM_ID);
+      await mintTo(connection, admin, feeMint.publicKey, source, admin, DEPOSIT_AMOUNT, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+      await program.methods
+        .initialize(feeFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: feeFund,
+          mint: feeMint.publicKey,
+          fundTokenAccount: feeVault,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .storeFunds(new anchor.BN(DEPOSIT_AMOUNT))
+        .accounts({
+          fundAccount: feeFund,
+          mint: feeMint.publicKey,
+          fromTokenAccount: source,
+          fundTokenAccount: feeVault,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      const signature = await program.methods
+        .reconcile()
+        .accounts({
+          fundAccount: feeFund,
+          fundTokenAccount: feeVault,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const withheld = (DEPOSIT_AMOUNT * FEE_BPS) / 10_000;
+      const fund = await program.account.fundAccount.fetch(feeFund);
+      expect(fund.totalFunds.toNumber()).to.equal(DEPOSIT_AMOUNT - withheld);
+
+      const event = await findEvent(signature, "VaultShortfallDetected");
+      expect(event.data.oldTotal.toNumber()).to.equal(DEPOSIT_AMOUNT);
+      expect(event.data.shortfall.toNumber()).to.equal(withheld);
+      expect(await findEvent(signature, "FundsReconciled")).to.be.undefined;
+    });
+
+    it("should fail to reconcile when not admin", async () => {
+      try {
+        await program.methods
+          .reconcile()
+          .accounts({
+            fundAccount,
+            fundTokenAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Deposit Withdrawal", () => {
+    let depositor: PublicKey;
+
+    const withdrawDeposit = (amount: number) =>
+      program.methods
+        .withdrawDeposit(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          depositor,
+          destinationTokenAccount: user2TokenAccount,
+          authority: user2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user2])
+        .rpc({ commitment: "confirmed" });
+
+    const setDepositsLocked = (locked: boolean) =>
+      program.methods
+        .setDepositsLocked(locked)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(() => {
+      [depositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), fundAccount.toBuffer(), user2.publicKey.toBuffer()],
+        program.programId
+      );
+    });
+
+    after(async () => {
+      await setDepositsLocked(false);
+    });
+
+    it("should let a depositor pull back part of their contribution", async () => {
+      const record = await program.account.depositor.fetch(depositor);
+      const fundBefore = await program.account.fundAccount.fetch(fundAccount);
+      const walletBefore = await getAccount(provider.connection, user2TokenAccount);
+      const amount = record.totalDeposited.toNumber() / 2;
+
+      const signature = await withdrawDeposit(amount);
+
+      const after = await program.account.depositor.fetch(depositor);
+      const fundAfter = await program.account.fundAccount.fetch(fundAccount);
+      const walletAfter = await getAccount(provider.connection, user2TokenAccount);
+      expect(after.totalDeposited.toNumber()).to.equal(record.totalDeposited.toNumber() - amount);
+      expect(fundAfter.totalFunds.toNumber()).to.equal(fundBefore.totalFunds.toNumber() - amount);
+      expect(Number(walletAfter.amount)).to.equal(Number(walletBefore.amount) + amount);
+
+      const event = await findEvent(signature, "DepositWithdrawn");
+      expect(event.data.depositor.toString()).to.equal(user2.publicKey.toString());
+      expect(event.data.remainingDeposit.toNumber()).to.equal(after.totalDeposited.toNumber());
+    });
+
+    it("should reject withdrawing more than the depositor contributed", async () => {
+      const record = await program.account.depositor.fetch(depositor);
+
+      try {
+        await withdrawDeposit(record.totalDeposited.toNumber() + 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WithdrawalExceedsDeposit");
+      }
+    });
+
+    it("should reject withdrawals while the fund is paused", async () => {
+      const setPaused = (paused: boolean) =>
+        program.methods
+          .setPaused(paused)
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+
+      await setPaused(true);
+
+      try {
+        await withdrawDeposit(1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundPaused");
+      } finally {
+        await setPaused(false);
+      }
+    });
+
+    it("should reject withdrawals while deposits are locked", async () => {
+      await setDepositsLocked(true);
+
+      try {
+        await withdrawDeposit(1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositsLocked");
+      }
+    });
+
+    it("should not close a depositor record with a balance while deposits are locked", async () => {
+      try {
+        await program.methods
+          .closeDepositor()
+          .accounts({
+            fundAccount,
+            depositor,
+            depositorWallet: user2.publicKey,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositorBalanceNotZero");
+      }
+    });
+
+    it("should fail to lock deposits when not admin", async () => {
+      try {
+        await program.methods
+          .setDepositsLocked(true)
+          .accounts({
+            fundAccount,
+            admin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Claims", () => {
+    const CLAIM_AMOUNT = 400_000;
+    const claimant = Keypair.generate();
+    let whitelistEntry: PublicKey;
+    let allocationLog: PublicKey;
+
+    const claimAddress = (nonce: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("claim"), fundAccount.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const createClaim = async () => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const claim = claimAddress(fund.claimNonce);
+      const signature = await program.methods
+        .createClaim(new anchor.BN(CLAIM_AMOUNT))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          claim,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      return { claim, signature };
+    };
+
+    const claimFunds = (claim: PublicKey, toTokenAccount: PublicKey, signer: Keypair) =>
+      program.methods
+        .claimFunds()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          allocationLog,
+          claim,
+          toTokenAccount,
+          feeTokenAccount: null,
+          recipient: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    const cancelClaim = (claim: PublicKey) =>
+      program.methods
+        .cancelClaim()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          claim,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const setGracePeriod = (graceSecs: number) =>
+      program.methods
+        .setClaimGracePeriod(new anchor.BN(graceSecs))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), claimant.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(claimant.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+
+      await program.methods
+        .addWhitelist(claimant.publicKey, "Claimant", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    after(async () => {
+      await setGracePeriod(0);
+    });
+
+    it("should earmark a claim for a recipient without a token account", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      const { claim, signature } = await createClaim();
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() - CLAIM_AMOUNT);
+      expect(after.totalClaimable.toNumber()).to.equal(before.totalClaimable.toNumber() + CLAIM_AMOUNT);
+
+      const record = await program.account.claimAccount.fetch(claim);
+      expect(record.recipient.toString()).to.equal(claimant.publicKey.toString());
+      expect(record.amount.toNumber()).to.equal(CLAIM_AMOUNT);
+      expect(record.claimed).to.be.false;
+
+      const event = await findEvent(signature, "ClaimCreated");
+      expect(event.data.claim.toString()).to.equal(claim.toString());
+    });
+
+    it("should let only the recipient claim, into an account they own", async () => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const claim = claimAddress(fund.claimNonce.subn(1));
+
+      try {
+        await claimFunds(claim, user1TokenAccount, user1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedClaimant");
+      }
+
+      const destination = await createAccount(provider.connection, claimant, mint, claimant.publicKey, Keypair.generate());
+      const signature = await claimFunds(claim, destination, claimant);
+
+      const received = await getAccount(provider.connection, destination);
+      expect(Number(received.amount)).to.equal(CLAIM_AMOUNT);
+      const record = await program.account.claimAccount.fetch(claim);
+      expect(record.claimed).to.be.true;
+
+      const event = await findEvent(signature, "FundsClaimed");
+      expect(event.data.destination.toString()).to.equal(destination.toString());
+
+      try {
+        await claimFunds(claim, destination, claimant);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ClaimAlreadySettled");
+      }
+    });
+
+    it("should only cancel an unclaimed claim after the grace period", async () => {
+      await setGracePeriod(3600);
+      const { claim } = await createClaim();
+
+      try {
+        await cancelClaim(claim);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ClaimGracePeriodActive");
+      }
+
+      await setGracePeriod(0);
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      const signature = await cancelClaim(claim);
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + CLAIM_AMOUNT);
+      expect(after.totalClaimable.toNumber()).to.equal(before.totalClaimable.toNumber() - CLAIM_AMOUNT);
+      expect(await provider.connection.getAccountInfo(claim)).to.be.null;
+
+      const event = await findEvent(signature, "ClaimCancelled");
+      expect(event.data.amount.toNumber()).to.equal(CLAIM_AMOUNT);
+    });
+  });
+
+  describe("Vesting", () => {
+    const VESTING_AMOUNT = 600_000;
+    const vestee = Keypair.generate();
+    let vesteeAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let allocationLog: PublicKey;
+
+    const createVesting = async (startTs: number, endTs: number) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const [schedule] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vesting"), fundAccount.toBuffer(), fund.vestingNonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createVesting(new anchor.BN(VESTING_AMOUNT), new anchor.BN(startTs), new anchor.BN(endTs), vestee.publicKey)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          schedule,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return schedule;
+    };
+
+    const claimVested = (schedule: PublicKey) =>
+      program.methods
+        .claimVested()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          allocationLog,
+          whitelistEntry,
+          schedule,
+          toTokenAccount: vesteeAccount,
+          feeTokenAccount: null,
+          recipient: vestee.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([vestee])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      vesteeAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, vestee.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), vestee.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(vestee.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+
+      await program.methods
+        .addWhitelist(vestee.publicKey, "Contributor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject a schedule that ends before it starts", async () => {
+      const now = Math.floor(Date.now() / 1000);
+
+      try {
+        await createVesting(now + 100, now + 100);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidVestingSchedule");
+      }
+    });
+
+    it("should count a new schedule against the daily limit", async () => {
+      const setDailyLimit = (limit: anchor.BN) =>
+        program.methods
+          .setDailyLimit(limit)
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      await setDailyLimit(new anchor.BN(1));
+
+      try {
+        const now = Math.floor(Date.now() / 1000);
+        await createVesting(now, now + 100);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DailyLimitExceeded");
+      } finally {
+        await setDailyLimit(fund.dailyLimit);
+      }
+    });
+
+    it("should release everything once the schedule has ended", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const schedule = await createVesting(now - 10, now + 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      const signature = await claimVested(schedule);
+
+      const balance = await getAccount(provider.connection, vesteeAccount);
+      expect(Number(balance.amount)).to.equal(VESTING_AMOUNT);
+      const record = await program.account.vestingSchedule.fetch(schedule);
+      expect(record.claimedAmount.toNumber()).to.equal(VESTING_AMOUNT);
+
+      const event = await findEvent(signature, "VestedFundsClaimed");
+      expect(event.data.amount.toNumber()).to.equal(VESTING_AMOUNT);
+
+      try {
+        await claimVested(schedule);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("NothingVested");
+      }
+    });
+
+    it("should return the unvested remainder when revoked before the start", async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const schedule = await createVesting(now + 3600, now + 7200);
+
+      try {
+        await claimVested(schedule);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("NothingVested");
+      }
+
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      await program.methods
+        .revokeVesting()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          schedule,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + VESTING_AMOUNT);
+      const record = await program.account.vestingSchedule.fetch(schedule);
+      expect(record.revoked).to.be.true;
+      expect(record.totalAmount.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("Locked Allocations", () => {
+    const LOCK_AMOUNT = 300_000;
+    const lockee = Keypair.generate();
+    let lockeeAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let allocationLog: PublicKey;
+    let lock: PublicKey;
+    let lockTokenAccount: PublicKey;
+
+    const allocateLocked = async (unlockAt: number) =>
+      program.methods
+        .allocateLocked(new anchor.BN(LOCK_AMOUNT), new anchor.BN(unlockAt))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          whitelistEntry,
+          lock,
+          lockTokenAccount,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    // Permissionless: the provider wallet cranks it, not the admin or recipient
+    const unlock = () =>
+      program.methods
+        .unlockAllocation()
+        .accounts({
+          fundAccount,
+          mint,
+          allocationLog,
+          lock,
+          lockTokenAccount,
+          recipientTokenAccount: lockeeAccount,
+          feeTokenAccount: null,
+          payer: admin.publicKey,
+          caller: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const cancel = () =>
+      program.methods
+        .cancelLockedAllocation()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          whitelistEntry,
+          lock,
+          lockTokenAccount,
+          payer: admin.publicKey,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    const lockeeBalance = async () => Number((await getAccount(provider.connection, lockeeAccount)).amount);
+
+    before(async () => {
+      lockeeAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, lockee.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), lockee.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+      [lock] = PublicKey.findProgramAddressSync(
+        [Buffer.from("lock"), fundAccount.toBuffer(), lockee.publicKey.toBuffer()],
+        program.programId
+      );
+      [lockTokenAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("lock_vault"), lock.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(lockee.publicKey, "Cliff Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should hold the tokens until unlock_at and release them at unlock_at", async () => {
+      const unlockAt = (await chainTime()) + 3;
+      await allocateLocked(unlockAt);
+
+      const held = await getAccount(provider.connection, lockTokenAccount);
+      expect(Number(held.amount)).to.equal(LOCK_AMOUNT);
+
+      // unlock_at - 1
+      await waitForChainTime(unlockAt - 1);
+      if ((await chainTime()) < unlockAt) {
+        try {
+          await unlock();
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("LockNotReleased");
+        }
+      }
+
+      // unlock_at
+      await waitForChainTime(unlockAt);
+      const before = await lockeeBalance();
+      const signature = await unlock();
+
+      expect(await lockeeBalance()).to.equal(before + LOCK_AMOUNT);
+      expect(await provider.connection.getAccountInfo(lock)).to.be.null;
+      expect(await provider.connection.getAccountInfo(lockTokenAccount)).to.be.null;
+
+      const event = await findEvent(signature, "LockedAllocationReleased");
+      expect(event.data.amount.toNumber()).to.equal(LOCK_AMOUNT);
+    });
+
+    it("should refuse to cancel well after unlock_at but still release", async () => {
+      const unlockAt = (await chainTime()) + 2;
+      await allocateLocked(unlockAt);
+      await waitForChainTime(unlockAt + 3);
+
+      try {
+        await cancel();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("LockAlreadyReleasable");
+      }
+
+      const before = await lockeeBalance();
+      await unlock();
+      expect(await lockeeBalance()).to.equal(before + LOCK_AMOUNT);
+    });
+
+    it("should not release a lock while the fund is paused", async () => {
+      const setPaused = (paused: boolean) =>
+        program.methods
+          .setPaused(paused)
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+
+      const unlockAt = (await chainTime()) + 2;
+      await allocateLocked(unlockAt);
+      await waitForChainTime(unlockAt);
+
+      await setPaused(true);
+      try {
+        await unlock();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundPaused");
+      } finally {
+        await setPaused(false);
+      }
+
+      const held = await getAccount(provider.connection, lockTokenAccount);
+      expect(Number(held.amount)).to.equal(LOCK_AMOUNT);
+
+      const before = await lockeeBalance();
+      await unlock();
+      expect(await lockeeBalance()).to.equal(before + LOCK_AMOUNT);
+    });
+
+    it("should let the admin reclaim a lock before unlock_at", async () => {
+      await allocateLocked((await chainTime()) + 3600);
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      await cancel();
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + LOCK_AMOUNT);
+      expect(after.openLocks).to.equal(before.openLocks - 1);
+      expect(await provider.connection.getAccountInfo(lock)).to.be.null;
+    });
+
+    it("should reject an unlock time that has already passed", async () => {
+      try {
+        await allocateLocked((await chainTime()) - 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidUnlockTime");
+      }
+    });
+  });
+
+  describe("Escrowed Allocations", () => {
+    const ESCROW_AMOUNT = 150_000;
+    const payee = Keypair.generate();
+    let payeeAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let allocationLog: PublicKey;
+
+    const escrowAccounts = (escrow: PublicKey) => ({
+      escrow,
+      escrowTokenAccount: PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow_vault"), escrow.toBuffer()],
+        program.programId
+      )[0],
+    });
+
+    const allocateEscrowed = async (releaseAfter: number) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const [escrow] = PublicKey.findProgramAddressSync(
+        [Buffer.from("escrow"), fundAccount.toBuffer(), fund.escrowNonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      const signature = await program.methods
+        .allocateEscrowed(new anchor.BN(ESCROW_AMOUNT), new anchor.BN(releaseAfter))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          whitelistEntry,
+          ...escrowAccounts(escrow),
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+      return { escrow, signature };
+    };
+
+    // Permissionless: the provider wallet cranks it, not the admin or recipient
+    const release = (escrow: PublicKey, auditRecord: PublicKey | null = null) =>
+      program.methods
+        .releaseEscrow()
+        .accounts({
+          fundAccount,
+          mint,
+          allocationLog,
+          ...escrowAccounts(escrow),
+          recipientTokenAccount: payeeAccount,
+          feeTokenAccount: null,
+          payer: admin.publicKey,
+          caller: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          auditRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const cancel = (escrow: PublicKey, auditRecord: PublicKey | null = null) =>
+      program.methods
+        .cancelEscrow()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          whitelistEntry,
+          ...escrowAccounts(escrow),
+          payer: admin.publicKey,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          auditRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const nextAuditRecord = async () => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("audit"), fundAccount.toBuffer(), fund.actionSequence.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    };
+
+    const payeeBalance = async () => Number((await getAccount(provider.connection, payeeAccount)).amount);
+
+    before(async () => {
+      payeeAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, payee.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), payee.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(payee.publicKey, "Escrow Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should hold several escrows for one recipient and release each at release_after", async () => {
+      const releaseAfter = (await chainTime()) + 3;
+      const first = await allocateEscrowed(releaseAfter);
+      const second = await allocateEscrowed(releaseAfter);
+      expect(first.escrow.toString()).to.not.equal(second.escrow.toString());
+
+      const created = await findEvent(first.signature, "EscrowCreated");
+      expect(created.data.amount.toNumber()).to.equal(ESCROW_AMOUNT);
+      expect(created.data.releaseAfter.toNumber()).to.equal(releaseAfter);
+      expect((await program.account.fundAccount.fetch(fundAccount)).openEscrows).to.be.at.least(2);
+
+      // release_after - 1
+      await waitForChainTime(releaseAfter - 1);
+      if ((await chainTime()) < releaseAfter) {
+        try {
+          await release(first.escrow);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("EscrowNotReleased");
+        }
+      }
+
+      // release_after
+      await waitForChainTime(releaseAfter);
+      const before = await payeeBalance();
+      const signature = await release(first.escrow);
+      await release(second.escrow);
+
+      expect(await payeeBalance()).to.equal(before + 2 * ESCROW_AMOUNT);
+      expect(await provider.connection.getAccountInfo(first.escrow)).to.be.null;
+      expect(await provider.connection.getAccountInfo(escrowAccounts(first.escrow).escrowTokenAccount)).to.be.null;
+
+      const event = await findEvent(signature, "EscrowReleased");
+      expect(event.data.recipient.toString()).to.equal(payee.publicKey.toString());
+      expect(event.data.amount.toNumber()).to.equal(ESCROW_AMOUNT);
+    });
+
+    it("should let the admin veto an escrow before release_after", async () => {
+      const { escrow } = await allocateEscrowed((await chainTime()) + 3600);
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      const entryBefore = await program.account.whitelistEntry.fetch(whitelistEntry);
+
+      const signature = await cancel(escrow);
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      const entryAfter = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + ESCROW_AMOUNT);
+      expect(after.openEscrows).to.equal(before.openEscrows - 1);
+      expect(entryAfter.totalAllocated.toNumber()).to.equal(entryBefore.totalAllocated.toNumber() - ESCROW_AMOUNT);
+      expect(await provider.connection.getAccountInfo(escrow)).to.be.null;
+
+      const event = await findEvent(signature, "EscrowCancelled");
+      expect(event.data.actor.toString()).to.equal(admin.publicKey.toString());
+      expect(event.data.amount.toNumber()).to.equal(ESCROW_AMOUNT);
+    });
+
+    it("should refuse to cancel once release_after has passed", async () => {
+      const releaseAfter = (await chainTime()) + 2;
+      const { escrow } = await allocateEscrowed(releaseAfter);
+      await waitForChainTime(releaseAfter);
+
+      try {
+        await cancel(escrow);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("EscrowAlreadyReleasable");
+      }
+
+      const before = await payeeBalance();
+      await release(escrow);
+      expect(await payeeBalance()).to.equal(before + ESCROW_AMOUNT);
+    });
+
+    it("should audit releases and vetoes while auditing is enabled", async () => {
+      const setAuditConfig = async (enabled: boolean) =>
+        program.methods
+          .setAuditConfig(enabled, new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+            auditRecord: await nextAuditRecord(),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+
+      const releaseAfter = (await chainTime()) + 2;
+      const released = await allocateEscrowed(releaseAfter);
+      const vetoed = await allocateEscrowed(releaseAfter + 3600);
+
+      await setAuditConfig(true);
+      try {
+        await waitForChainTime(releaseAfter);
+        try {
+          await release(released.escrow);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("AuditRecordRequired");
+        }
+
+        const releaseRecord = await nextAuditRecord();
+        await release(released.escrow, releaseRecord);
+        const cancelRecord = await nextAuditRecord();
+        await cancel(vetoed.escrow, cancelRecord);
+
+        const releaseData = await program.account.auditRecord.fetch(releaseRecord);
+        expect(releaseData.action).to.deep.equal({ releaseEscrow: {} });
+        expect(releaseData.actor.toString()).to.equal(provider.wallet.publicKey.toString());
+        expect(releaseData.target.toString()).to.equal(payee.publicKey.toString());
+        expect(releaseData.amount.toNumber()).to.equal(ESCROW_AMOUNT);
+
+        const cancelData = await program.account.auditRecord.fetch(cancelRecord);
+        expect(cancelData.action).to.deep.equal({ cancelEscrow: {} });
+        expect(cancelData.actor.toString()).to.equal(admin.publicKey.toString());
+        expect(cancelData.sequence.toNumber()).to.equal(releaseData.sequence.toNumber() + 1);
+      } finally {
+        await setAuditConfig(false);
+      }
+    });
+
+    it("should reject a release time that has already passed", async () => {
+      try {
+        await allocateEscrowed((await chainTime()) - 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidReleaseTime");
+      }
+    });
+  });
+
+  describe("Recurring Allocations", () => {
+    const RECURRING_AMOUNT = 200_000;
+    const payee = Keypair.generate();
+    let payeeAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let allocationLog: PublicKey;
+
+    const createRecurring = async (intervalSecs: number, firstRunAt: number, runs: number) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const [schedule] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recurring"), fundAccount.toBuffer(), fund.recurringNonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .createRecurring(new anchor.BN(RECURRING_AMOUNT), new anchor.BN(intervalSecs), new anchor.BN(firstRunAt), runs)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          schedule,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return schedule;
+    };
+
+    // Permissionless crank, sent by the provider wallet
+    const execute = (schedule: PublicKey) =>
+      program.methods
+        .executeRecurring()
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          allocationLog,
+          schedule,
+          whitelistEntry,
+          toTokenAccount: payeeAccount,
+          feeTokenAccount: null,
+          payer: admin.publicKey,
+          caller: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    const toggle = (isActive: boolean) =>
+      program.methods
+        .toggleWhitelist(isActive)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const payeeBalance = async () => Number((await getAccount(provider.connection, payeeAccount)).amount);
+
+    before(async () => {
+      payeeAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, payee.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), payee.publicKey.toBuffer()],
+        program.programId
+      );
+      [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(payee.publicKey, "Monthly Payee", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should fail with RecurringNotDue when cranked early", async () => {
+      const schedule = await createRecurring(3600, (await chainTime()) + 3600, 1);
+
+      try {
+        await execute(schedule);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("RecurringNotDue");
+      }
+    });
+
+    it("should pay each run on schedule and close once exhausted", async () => {
+      const firstRunAt = await chainTime();
+      const schedule = await createRecurring(2, firstRunAt, 2);
+      const before = await payeeBalance();
+
+      await execute(schedule);
+      expect(await payeeBalance()).to.equal(before + RECURRING_AMOUNT);
+      const record = await program.account.recurringAllocation.fetch(schedule);
+      expect(record.nextRunAt.toNumber()).to.equal(firstRunAt + 2);
+      expect(record.remainingRuns).to.equal(1);
+
+      await waitForChainTime(firstRunAt + 2);
+      await execute(schedule);
+      expect(await payeeBalance()).to.equal(before + 2 * RECURRING_AMOUNT);
+      expect(await provider.connection.getAccountInfo(schedule)).to.be.null;
+    });
+
+    it("should check the whitelist at execution time", async () => {
+      const schedule = await createRecurring(3600, await chainTime(), 1);
+      await toggle(false);
+
+      try {
+        await execute(schedule);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("RecipientNotWhitelisted");
+      } finally {
+        await toggle(true);
+      }
+    });
+  });
+
+  describe("Token Recovery", () => {
+    const STRAY_AMOUNT = 750_000;
+
+    it("should recover a foreign token sent to an account the fund owns", async () => {
+      const connection = provider.connection;
+      const foreignMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      const strayAccount = await createAccount(connection, admin, foreignMint, fundAccount, Keypair.generate());
+      const destination = await createAssociatedTokenAccount(connection, admin, foreignMint, admin.publicKey);
+      await mintTo(connection, admin, foreignMint, strayAccount, admin, STRAY_AMOUNT);
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      const signature = await program.methods
+        .recoverToken(new anchor.BN(STRAY_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint: foreignMint,
+          sourceTokenAccount: strayAccount,
+          destinationTokenAccount: destination,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const recovered = await getAccount(connection, destination);
+      expect(Number(recovered.amount)).to.equal(STRAY_AMOUNT);
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber());
+
+      const event = await findEvent(signature, "TokensRecovered");
+      expect(event.data.mint.toString()).to.equal(foreignMint.toString());
+      expect(event.data.destination.toString()).to.equal(destination.toString());
+      expect(event.data.amount.toNumber()).to.equal(STRAY_AMOUNT);
+    });
+
+    it("should refuse to recover the fund's own mint from the vault", async () => {
+      try {
+        await program.methods
+          .recoverToken(new anchor.BN(1))
+          .accounts({
+            fundAccount,
+            mint,
+            sourceTokenAccount: fundTokenAccount,
+            destinationTokenAccount: user1TokenAccount,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("CannotRecoverFundMint");
+      }
+    });
+  });
+
+  describe("Allocation Memos", () => {
+    const MEMO_PROGRAM_ID = new PublicKey("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocateWithMemo = (memo: string, reasonCode: number | null) =>
+      program.methods
+        .allocateFundsWithMemo(new anchor.BN(100_000), memo, reasonCode)
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          memoProgram: MEMO_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Audited Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should carry the memo and reason code into the event and the transaction", async () => {
+      const memo = "Invoice 2024-117";
+      const signature = await allocateWithMemo(memo, 7);
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.memo).to.equal(memo);
+      expect(event.data.reasonCode).to.equal(7);
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      expect(tx.meta.logMessages.some((log) => log.includes(memo))).to.be.true;
+    });
+
+    it("should allow an empty memo without a reason code", async () => {
+      const signature = await allocateWithMemo("", null);
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.memo).to.equal("");
+      expect(event.data.reasonCode).to.be.null;
+    });
+
+    it("should reject a memo over 128 bytes", async () => {
+      try {
+        await allocateWithMemo("x".repeat(129), null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("MemoTooLong");
+      }
+    });
+  });
+
+  describe("Allocation Nonces", () => {
+    const recipient = Keypair.generate();
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const nonceAddress = (nonce: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("nonce"), fundAccount.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const allocateWithNonce = (nonce: anchor.BN, nonceRecord: PublicKey | null) =>
+      program.methods
+        .allocateFundsWithNonce(new anchor.BN(100_000), nonce)
+        .accounts({
+          allocate: {
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+          nonceRecord,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Idempotent Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should record a nonzero nonce and reject a retry with it", async () => {
+      const nonce = new anchor.BN(Date.now());
+      const nonceRecord = nonceAddress(nonce);
+
+      await allocateWithNonce(nonce, nonceRecord);
+
+      const record = await program.account.allocationNonce.fetch(nonceRecord);
+      expect(record.nonce.toString()).to.equal(nonce.toString());
+      expect(record.recipient.toString()).to.equal(recipient.publicKey.toString());
+      expect(record.amount.toNumber()).to.equal(100_000);
+
+      const before = await getAccount(provider.connection, recipientAccount);
+      try {
+        await allocateWithNonce(nonce, nonceRecord);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DuplicateAllocationNonce");
+      }
+      const after = await getAccount(provider.connection, recipientAccount);
+      expect(after.amount.toString()).to.equal(before.amount.toString());
+    });
+
+    it("should allocate without a record when the nonce is zero", async () => {
+      const before = await getAccount(provider.connection, recipientAccount);
+
+      await allocateWithNonce(new anchor.BN(0), null);
+      await allocateWithNonce(new anchor.BN(0), null);
+
+      const after = await getAccount(provider.connection, recipientAccount);
+      expect(Number(after.amount - before.amount)).to.equal(200_000);
+    });
+
+    it("should require a record for a nonzero nonce", async () => {
+      try {
+        await allocateWithNonce(new anchor.BN(42), null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationNonceRequired");
+      }
+    });
+
+    it("should keep a nonce record until the retention window has passed", async () => {
+      const nonce = new anchor.BN(Date.now() + 1);
+      const nonceRecord = nonceAddress(nonce);
+      await allocateWithNonce(nonce, nonceRecord);
+
+      try {
+        await program.methods
+          .closeAllocationNonce()
+          .accounts({
+            fundAccount,
+            nonceRecord,
+            payer: admin.publicKey,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationNonceTooRecent");
+      }
+    });
+  });
+
+  describe("Allocation Expiry", () => {
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocateWithExpiry = (validUntil: number, minVaultBalanceAfter: anchor.BN) =>
+      program.methods
+        .allocateFundsWithExpiry(new anchor.BN(100_000), new anchor.BN(validUntil), minVaultBalanceAfter)
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Relayed Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should allocate before valid_until and report it in the event", async () => {
+      const validUntil = (await chainTime()) + 60;
+      const signature = await allocateWithExpiry(validUntil, new anchor.BN(0));
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.validUntil.toNumber()).to.equal(validUntil);
+    });
+
+    it("should reject an allocation past valid_until", async () => {
+      const validUntil = (await chainTime()) - 1;
+      try {
+        await allocateWithExpiry(validUntil, new anchor.BN(0));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationExpired");
+      }
+    });
+
+    it("should treat a zero valid_until as no expiry", async () => {
+      const signature = await allocateWithExpiry(0, new anchor.BN(0));
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.validUntil.toNumber()).to.equal(0);
+    });
+
+    it("should enforce the minimum vault balance after the transfer", async () => {
+      const vault = await getAccount(provider.connection, fundTokenAccount);
+      const remaining = new anchor.BN((vault.amount - BigInt(100_000)).toString());
+
+      try {
+        await allocateWithExpiry(0, remaining.addn(1));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("VaultBalanceBelowMinimum");
+      }
+
+      await allocateWithExpiry(0, remaining);
+    });
+  });
+
+  describe("Self Allocation", () => {
+    let selfEntry: PublicKey;
+
+    before(async () => {
+      // Whitelisting the fund's own PDA is what used to let its vault be
+      // passed as the recipient, shrinking total_funds with no transfer out
+      [selfEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), fundAccount.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(fundAccount, "Fund Itself", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry: selfEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject allocating into the fund's own vault", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(1_000_000))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: fundTokenAccount,
+            whitelistEntry: selfEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("SelfAllocationForbidden");
+      }
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toString()).to.equal(before.totalFunds.toString());
+    });
+
+    it("should reject the fund's vault inside a batch", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+
+      try {
+        await program.methods
+          .allocateFundsBatch([new anchor.BN(1_000_000)])
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([
+            { pubkey: selfEntry, isSigner: false, isWritable: true },
+            { pubkey: fundTokenAccount, isSigner: false, isWritable: true },
+          ])
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("SelfAllocationForbidden");
+      }
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toString()).to.equal(before.totalFunds.toString());
+    });
+  });
+
+  describe("Allocation Counters", () => {
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Counted Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should start a new entry with no allocations", async () => {
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.allocationCount).to.equal(0);
+      expect(entry.lastAllocationAmount.toNumber()).to.equal(0);
+      expect(entry.lastAllocatedAt.toNumber()).to.equal(0);
+    });
+
+    it("should count each payout and keep the latest amount", async () => {
+      await allocate(100_000);
+      const signature = await allocate(250_000);
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.allocationCount).to.equal(2);
+      expect(entry.lastAllocationAmount.toNumber()).to.equal(250_000);
+      expect(entry.lastAllocatedAt.toNumber()).to.be.greaterThan(0);
+
+      const event = await findEvent(signature, "FundsAllocated");
+      expect(event.data.allocationCount).to.equal(2);
+    });
+  });
+
+  describe("Token Account Whitelist", () => {
+    // Stands in for a program-controlled vault whose owner is a PDA
+    const vaultOwner = Keypair.generate();
+    let vaultAccount: PublicKey;
+    let siblingAccount: PublicKey;
+    let accountEntry: PublicKey;
+
+    const allocateTo = (toTokenAccount: PublicKey) =>
+      program.methods
+        .allocateFunds(new anchor.BN(100_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount,
+          whitelistEntry: accountEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      vaultAccount = await createAccount(provider.connection, admin, mint, vaultOwner.publicKey, Keypair.generate());
+      siblingAccount = await createAccount(provider.connection, admin, mint, vaultOwner.publicKey, Keypair.generate());
+      [accountEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist_account"), Buffer.from(fundId), vaultAccount.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelistTokenAccount("DAO Treasury", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          tokenAccount: vaultAccount,
+          whitelistEntry: accountEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should bind the entry to the token account", async () => {
+      const entry = await program.account.whitelistEntry.fetch(accountEntry);
+      expect(entry.address.toString()).to.equal(vaultAccount.toString());
+      expect(entry.targetKind).to.deep.equal({ tokenAccount: {} });
+    });
+
+    it("should pay the bound token account", async () => {
+      const before = await getAccount(provider.connection, vaultAccount);
+      await allocateTo(vaultAccount);
+      const after = await getAccount(provider.connection, vaultAccount);
+      expect(Number(after.amount - before.amount)).to.equal(100_000);
+    });
+
+    it("should not pay another token account of the same owner", async () => {
+      try {
+        await allocateTo(siblingAccount);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("should keep owner entries in a separate namespace", async () => {
+      // An owner entry for the same pubkey lands on a different PDA
+      const [ownerEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), vaultAccount.toBuffer()],
+        program.programId
+      );
+      expect(ownerEntry.toString()).to.not.equal(accountEntry.toString());
+
+      await program.methods
+        .addWhitelist(vaultAccount, "Same Key As Owner", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry: ownerEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const entry = await program.account.whitelistEntry.fetch(ownerEntry);
+      expect(entry.targetKind).to.deep.equal({ owner: {} });
+    });
+
+    it("should reject token account entries for owner-only flows", async () => {
+      const [lock] = PublicKey.findProgramAddressSync(
+        [Buffer.from("lock"), fundAccount.toBuffer(), vaultAccount.toBuffer()],
+        program.programId
+      );
+      const [lockTokenAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("lock_vault"), lock.toBuffer()],
+        program.programId
+      );
+      try {
+        await program.methods
+          .allocateLocked(new anchor.BN(100_000), new anchor.BN((await chainTime()) + 3600))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            whitelistEntry: accountEntry,
+            lock,
+            lockTokenAccount,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("OwnerEntryRequired");
+      }
+    });
+  });
+
+  describe("Lamport Sweep", () => {
+    const STRAY_LAMPORTS = 5_000_000;
+
+    const sweep = (destination: PublicKey) =>
+      program.methods
+        .sweepLamports()
+        .accounts({
+          fundAccount,
+          destination,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    it("should return lamports sent to the fund PDA and keep it rent-exempt", async () => {
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: admin.publicKey,
+            toPubkey: fundAccount,
+            lamports: STRAY_LAMPORTS,
+          })
+        ),
+        [admin]
+      );
+
+      const destination = Keypair.generate().publicKey;
+      const signature = await sweep(destination);
+
+      const fundInfo = await provider.connection.getAccountInfo(fundAccount);
+      const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(fundInfo.data.length);
+      expect(fundInfo.lamports).to.equal(rentExempt);
+
+      const event = await findEvent(signature, "LamportsSwept");
+      expect(event.data.amount.toNumber()).to.be.at.least(STRAY_LAMPORTS);
+      expect(await provider.connection.getBalance(destination)).to.equal(event.data.amount.toNumber());
+    });
+
+    it("should refuse when nothing is above rent exemption", async () => {
+      try {
+        await sweep(admin.publicKey);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("NothingToSweep");
+      }
+    });
+
+    it("should reject a non-admin", async () => {
+      const stranger = Keypair.generate();
+      try {
+        await program.methods
+          .sweepLamports()
+          .accounts({
+            fundAccount,
+            destination: stranger.publicKey,
+            admin: stranger.publicKey,
+          })
+          .signers([stranger])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Signer Rate Limit", () => {
+    const WINDOW_SECS = 4;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let signerRateLimit: PublicKey;
+
+    const setRateLimit = (max: number, windowSecs: number) =>
+      program.methods
+        .setAllocationRateLimit(max, new anchor.BN(windowSecs))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const allocate = (rateLimit: PublicKey | null) =>
+      program.methods
+        .allocateFunds(new anchor.BN(10_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          signerRateLimit: rateLimit,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      [signerRateLimit] = PublicKey.findProgramAddressSync(
+        [Buffer.from("signer_rate"), fundAccount.toBuffer(), admin.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Rate Limited Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setRateLimit(2, WINDOW_SECS);
+    });
+
+    after(async () => {
+      await setRateLimit(0, 3600);
+    });
+
+    it("should require the signer's rate limit account while the limit is set", async () => {
+      try {
+        await allocate(null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("SignerRateLimitRequired");
+      }
+    });
+
+    it("should reject allocations past the limit and reset when the window ends", async () => {
+      await allocate(signerRateLimit);
+      await allocate(signerRateLimit);
+
+      const state = await program.account.signerRateLimit.fetch(signerRateLimit);
+      expect(state.allocationsInWindow).to.equal(2);
+
+      try {
+        await allocate(signerRateLimit);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AllocationRateLimitExceeded");
+      }
+
+      // The window is a fixed bucket from its first allocation
+      await waitForChainTime(state.windowStart.toNumber() + WINDOW_SECS);
+      await allocate(signerRateLimit);
+
+      const reset = await program.account.signerRateLimit.fetch(signerRateLimit);
+      expect(reset.allocationsInWindow).to.equal(1);
+      expect(reset.windowStart.toNumber()).to.be.at.least(state.windowStart.toNumber() + WINDOW_SECS);
+    });
+  });
+
+  describe("Audit Log", () => {
+    const auditAddress = (sequence: anchor.BN) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("audit"), fundAccount.toBuffer(), sequence.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+
+    const setAuditConfig = async (enabled: boolean, retentionSecs: number) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const auditRecord = auditAddress(fund.actionSequence);
+      await program.methods
+        .setAuditConfig(enabled, new anchor.BN(retentionSecs))
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+          auditRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return auditRecord;
+    };
+
+    const setPausedAudited = async (paused: boolean) => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      const auditRecord = auditAddress(fund.actionSequence);
+      await program.methods
+        .setPaused(paused)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+          auditRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      return auditRecord;
+    };
+
+    before(async () => {
+      await setAuditConfig(true, 0);
+    });
+
+    after(async () => {
+      await setAuditConfig(false, 0);
+    });
+
+    it("should require an audit record while auditing is enabled", async () => {
+      try {
+        await program.methods
+          .setPaused(true)
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+            auditRecord: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordRequired");
+      }
+    });
+
+    it("should write sequenced records and prune them after the retention period", async () => {
+      const sequenceBefore = (await program.account.fundAccount.fetch(fundAccount)).actionSequence.toNumber();
+
+      const pauseRecord = await setPausedAudited(true);
+      const unpauseRecord = await setPausedAudited(false);
+
+      const pauseData = await program.account.auditRecord.fetch(pauseRecord);
+      const unpauseData = await program.account.auditRecord.fetch(unpauseRecord);
+      expect(pauseData.sequence.toNumber()).to.equal(sequenceBefore);
+      expect(pauseData.action).to.deep.equal({ pause: {} });
+      expect(pauseData.actor.toString()).to.equal(admin.publicKey.toString());
+      expect(pauseData.fund.toString()).to.equal(fundAccount.toString());
+      expect(unpauseData.sequence.toNumber()).to.equal(sequenceBefore + 1);
+      expect(unpauseData.action).to.deep.equal({ unpause: {} });
+      expect((await program.account.fundAccount.fetch(fundAccount)).actionSequence.toNumber()).to.equal(sequenceBefore + 2);
+
+      // A long retention period keeps the records in place
+      await setAuditConfig(true, 86_400);
+      try {
+        await program.methods
+          .pruneAuditRecords()
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .remainingAccounts([
+            { pubkey: pauseRecord, isSigner: false, isWritable: true },
+            { pubkey: admin.publicKey, isSigner: false, isWritable: true },
+          ])
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordTooRecent");
+      }
+
+      await setAuditConfig(true, 0);
+      const signature = await program.methods
+        .pruneAuditRecords()
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .remainingAccounts([
+          { pubkey: pauseRecord, isSigner: false, isWritable: true },
+          { pubkey: admin.publicKey, isSigner: false, isWritable: true },
+          { pubkey: unpauseRecord, isSigner: false, isWritable: true },
+          { pubkey: admin.publicKey, isSigner: false, isWritable: true },
+        ])
+        .signers([admin])
+        .rpc();
+
+      const event = await findEvent(signature, "AuditRecordsPruned");
+      expect(event.data.pruned).to.equal(2);
+      expect(await provider.connection.getAccountInfo(pauseRecord)).to.be.null;
+      expect(await provider.connection.getAccountInfo(unpauseRecord)).to.be.null;
+    });
+
+    it("should audit withdrawals and threshold changes", async () => {
+      const withdraw = (auditRecord: PublicKey | null) =>
+        program.methods
+          .withdrawFunds(new anchor.BN(1))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            destinationTokenAccount: user1TokenAccount,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            auditRecord,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+
+      try {
+        await withdraw(null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordRequired");
+      }
+
+      let fund = await program.account.fundAccount.fetch(fundAccount);
+      const withdrawRecord = auditAddress(fund.actionSequence);
+      await withdraw(withdrawRecord);
+
+      const withdrawData = await program.account.auditRecord.fetch(withdrawRecord);
+      expect(withdrawData.action).to.deep.equal({ withdraw: {} });
+      expect(withdrawData.target.toString()).to.equal(user1.publicKey.toString());
+      expect(withdrawData.amount.toNumber()).to.equal(1);
+
+      fund = await program.account.fundAccount.fetch(fundAccount);
+      const thresholdRecord = auditAddress(fund.actionSequence);
+      await program.methods
+        .setAdminThreshold(1)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+          auditRecord: thresholdRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const thresholdData = await program.account.auditRecord.fetch(thresholdRecord);
+      expect(thresholdData.action).to.deep.equal({ setThreshold: {} });
+      expect(thresholdData.sequence.toNumber()).to.equal(withdrawData.sequence.toNumber() + 1);
+      expect(thresholdData.amount.toNumber()).to.equal(1);
+    });
+
+    it("should audit whitelist setting changes", async () => {
+      const CAP = 5_000_000;
+      const recipient = Keypair.generate();
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      let fund = await program.account.fundAccount.fetch(fundAccount);
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Audited Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          auditRecord: auditAddress(fund.actionSequence),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const setWeight = (auditRecord: PublicKey | null) =>
+        program.methods
+          .setWhitelistWeight(3)
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+            auditRecord,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+
+      try {
+        await setWeight(null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordRequired");
+      }
+
+      fund = await program.account.fundAccount.fetch(fundAccount);
+      const weightRecord = auditAddress(fund.actionSequence);
+      await setWeight(weightRecord);
+
+      const weightData = await program.account.auditRecord.fetch(weightRecord);
+      expect(weightData.action).to.deep.equal({ whitelistWeightUpdated: {} });
+      expect(weightData.target.toString()).to.equal(recipient.publicKey.toString());
+
+      fund = await program.account.fundAccount.fetch(fundAccount);
+      const capRecord = auditAddress(fund.actionSequence);
+      await program.methods
+        .updateAllocationCap(new anchor.BN(CAP))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          auditRecord: capRecord,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const capData = await program.account.auditRecord.fetch(capRecord);
+      expect(capData.action).to.deep.equal({ whitelistCapUpdated: {} });
+      expect(capData.sequence.toNumber()).to.equal(weightData.sequence.toNumber() + 1);
+      expect(capData.amount.toNumber()).to.equal(CAP);
+    });
+
+    it("should audit turning auditing off", async () => {
+      try {
+        await program.methods
+          .setAuditConfig(false, new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+            auditRecord: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordRequired");
+      }
+
+      const record = await setAuditConfig(false, 0);
+
+      const data = await program.account.auditRecord.fetch(record);
+      expect(data.action).to.deep.equal({ setAuditConfig: {} });
+      expect((await program.account.fundAccount.fetch(fundAccount)).auditEnabled).to.be.false;
+
+      await setAuditConfig(true, 0);
+    });
+
+    it("should reject pruning a record into another payer's account", async () => {
+      const record = await setPausedAudited(false);
+
+      try {
+        await program.methods
+          .pruneAuditRecords()
+          .accounts({
+            fundAccount,
+            admin: admin.publicKey,
+          })
+          .remainingAccounts([
+            { pubkey: record, isSigner: false, isWritable: true },
+            { pubkey: user1.publicKey, isSigner: false, isWritable: true },
+          ])
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuditRecordMismatch");
+      }
+    });
+  });
+
+  describe("Delegated Authority", () => {
+    const DELEGATE_CAP = 2_000_000;
+    const deputy = Keypair.generate();
+    let delegation: PublicKey;
+    let recipient: Keypair;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+    let expiresAt: number;
+
+    const allocateAsDeputy = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          delegation,
+          authority: deputy.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([deputy])
+        .rpc();
+
+    before(async () => {
+      recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      [delegation] = PublicKey.findProgramAddressSync(
+        [Buffer.from("delegation"), fundAccount.toBuffer(), deputy.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Delegate Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject a delegation that is already expired", async () => {
+      try {
+        await program.methods
+          .createDelegation(deputy.publicKey, new anchor.BN((await chainTime()) - 1), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            delegation,
+            admin: admin.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidDelegationExpiry");
+      }
+    });
+
+    it("should let an unexpired delegate allocate within its cap", async () => {
+      expiresAt = (await chainTime()) + 6;
+      await program.methods
+        .createDelegation(deputy.publicKey, new anchor.BN(expiresAt), new anchor.BN(DELEGATE_CAP))
+        .accounts({
+          fundAccount,
+          delegation,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const before = await getAccount(provider.connection, recipientAccount);
+      await allocateAsDeputy(DELEGATE_CAP);
+      const after = await getAccount(provider.connection, recipientAccount);
+      expect(Number(after.amount) - Number(before.amount)).to.equal(DELEGATE_CAP);
+
+      try {
+        await allocateAsDeputy(DELEGATE_CAP + 1);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAllocator");
+      }
+    });
+
+    it("should not give the delegate whitelist powers", async () => {
+      const other = Keypair.generate();
+      const [otherEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), other.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .addWhitelist(other.publicKey, "Deputy Pick", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry: otherEntry,
+            admin: deputy.publicKey,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([deputy])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+
+    it("should stop honouring the delegation once it expires", async () => {
+      await waitForChainTime(expiresAt);
+
+      try {
+        await allocateAsDeputy(1_000_000);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAllocator");
+      }
+    });
+
+    it("should let the admin revoke the delegation and reclaim its rent", async () => {
+      const signature = await program.methods
+        .revokeDelegation()
+        .accounts({
+          fundAccount,
+          delegation,
+          payer: admin.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "DelegationRevoked");
+      expect(event.data.delegate.toString()).to.equal(deputy.publicKey.toString());
+      expect(await provider.connection.getAccountInfo(delegation)).to.be.null;
+    });
+  });
+
+  describe("Period Budgets", () => {
+    const PERIOD_LIMIT = 3_000_000;
+    const PERIOD_SECS = 4;
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocate = (amount: number) =>
+      program.methods
+        .allocateFunds(new anchor.BN(amount))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Budget Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject a limit without a period", async () => {
+      try {
+        await program.methods
+          .setPeriodBudget(new anchor.BN(PERIOD_LIMIT), new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidBudgetPeriod");
+      }
+    });
+
+    it("should cap spending within a period", async () => {
+      await program.methods
+        .setPeriodBudget(new anchor.BN(PERIOD_LIMIT), new anchor.BN(PERIOD_SECS))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await allocate(2_000_000);
+
+      try {
+        await allocate(2_000_000);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("PeriodBudgetExceeded");
+      }
+
+      // Spending up to the limit exactly is allowed
+      await allocate(1_000_000);
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.spentInPeriod.toNumber()).to.equal(PERIOD_LIMIT);
+    });
+
+    it("should roll the period over at exactly period_secs", async () => {
+      const { periodStart } = await program.account.whitelistEntry.fetch(whitelistEntry);
+      await waitForChainTime(periodStart.toNumber() + PERIOD_SECS);
+
+      await allocate(PERIOD_LIMIT);
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.periodStart.toNumber()).to.be.at.least(periodStart.toNumber() + PERIOD_SECS);
+      expect(entry.spentInPeriod.toNumber()).to.equal(PERIOD_LIMIT);
+    });
+
+    it("should charge an allocation straddling a rollover to the new period only", async () => {
+      const { periodStart } = await program.account.whitelistEntry.fetch(whitelistEntry);
+
+      // Rejected while the exhausted period is still running
+      try {
+        await allocate(2_000_000);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("PeriodBudgetExceeded");
+      }
+
+      await waitForChainTime(periodStart.toNumber() + PERIOD_SECS);
+      await allocate(2_000_000);
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.spentInPeriod.toNumber()).to.equal(2_000_000);
+    });
+  });
+
+  describe("Whitelist Suspension", () => {
+    const REASON = "Pending KYC review";
+    let recipientAccount: PublicKey;
+    let whitelistEntry: PublicKey;
+
+    const allocate = () =>
+      program.methods
+        .allocateFunds(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          toTokenAccount: recipientAccount,
+          whitelistEntry,
+          authority: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+
+    before(async () => {
+      const recipient = Keypair.generate();
+      recipientAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recipient.publicKey);
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Suspended Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should reject a reason longer than 128 characters", async () => {
+      try {
+        await program.methods
+          .suspendWhitelist("x".repeat(129))
+          .accounts({
+            fundAccount,
+            whitelistEntry,
+            admin: admin.publicKey,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("SuspensionReasonTooLong");
+      }
+    });
+
+    it("should block allocations to a suspended entry with its own error", async () => {
+      const signature = await program.methods
+        .suspendWhitelist(REASON)
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "WhitelistEntrySuspended");
+      expect(event.data.reason).to.equal(REASON);
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.suspended).to.be.true;
+      expect(entry.isActive).to.be.true;
+      expect(entry.suspensionReason).to.equal(REASON);
+      expect(entry.suspendedAt.toNumber()).to.be.greaterThan(0);
+
+      try {
+        await allocate();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistSuspended");
+      }
+    });
+
+    it("should clear the suspension and allow allocations again", async () => {
+      await program.methods
+        .unsuspendWhitelist()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const entry = await program.account.whitelistEntry.fetch(whitelistEntry);
+      expect(entry.suspended).to.be.false;
+      expect(entry.suspensionReason).to.equal("");
+      expect(entry.suspendedAt.toNumber()).to.equal(0);
+
+      await allocate();
+    });
+  });
+
+  describe("Restricted Deposits", () => {
+    const RESTRICTED_DEPOSIT = 10_000;
+    const listed = Keypair.generate();
+    const unlisted = Keypair.generate();
+    let listedTokenAccount: PublicKey;
+    let unlistedTokenAccount: PublicKey;
+    let listedEntry: PublicKey;
+
+    const setRestricted = (restricted: boolean) =>
+      program.methods
+        .setRestrictedDeposits(restricted)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const depositAs = (depositor: Keypair, fromTokenAccount: PublicKey, depositorEntry: PublicKey | null) =>
+      program.methods
+        .storeFunds(new anchor.BN(RESTRICTED_DEPOSIT))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount,
+          fundTokenAccount,
+          depositorEntry,
+          authority: depositor.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+    before(async () => {
+      const connection = provider.connection;
+      for (const depositor of [listed, unlisted]) {
+        await connection.confirmTransaction(
+          await connection.requestAirdrop(depositor.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+          "confirmed"
+        );
+      }
+      listedTokenAccount = await createAssociatedTokenAccount(connection, admin, mint, listed.publicKey);
+      unlistedTokenAccount = await createAssociatedTokenAccount(connection, admin, mint, unlisted.publicKey);
+      await mintTo(connection, admin, mint, listedTokenAccount, admin, RESTRICTED_DEPOSIT * 4);
+      await mintTo(connection, admin, mint, unlistedTokenAccount, admin, RESTRICTED_DEPOSIT * 4);
+
+      [listedEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), listed.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .addWhitelist(listed.publicKey, "Known Counterparty", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry: listedEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .setWhitelistCanDeposit(true)
+        .accounts({
+          fundAccount,
+          whitelistEntry: listedEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    after(async () => {
+      await setRestricted(false);
+    });
+
+    it("should accept an unlisted depositor while unrestricted", async () => {
+      await depositAs(unlisted, unlistedTokenAccount, null);
+    });
+
+    it("should accept a listed depositor while unrestricted", async () => {
+      await depositAs(listed, listedTokenAccount, listedEntry);
+    });
+
+    it("should reject an unlisted depositor while restricted", async () => {
+      await setRestricted(true);
+
+      try {
+        await depositAs(unlisted, unlistedTokenAccount, null);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositorNotWhitelisted");
+      }
+    });
+
+    it("should accept a listed depositor while restricted", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      await depositAs(listed, listedTokenAccount, listedEntry);
+
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.toNumber()).to.equal(before.totalFunds.toNumber() + RESTRICTED_DEPOSIT);
+    });
+
+    it("should reject a listed depositor without can_deposit while restricted", async () => {
+      await program.methods
+        .setWhitelistCanDeposit(false)
+        .accounts({
+          fundAccount,
+          whitelistEntry: listedEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await depositAs(listed, listedTokenAccount, listedEntry);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositorNotWhitelisted");
+      }
+    });
+  });
+
+  describe("Open Deposits", () => {
+    const DEPOSIT = 10_000;
+    const outsider = Keypair.generate();
+    let outsiderTokenAccount: PublicKey;
+    let adminTokenAccount: PublicKey;
+
+    const setOpen = (open: boolean) =>
+      program.methods
+        .setOpenDeposits(open)
+        .accounts({
+          fundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const depositAs = (depositor: Keypair, fromTokenAccount: PublicKey) =>
+      program.methods
+        .storeFunds(new anchor.BN(DEPOSIT))
+        .accounts({
+          fundAccount,
+          mint,
+          fromTokenAccount,
+          fundTokenAccount,
+          depositorEntry: null,
+          authority: depositor.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([depositor])
+        .rpc();
+
+    before(async () => {
+      const connection = provider.connection;
+      await connection.confirmTransaction(
+        await connection.requestAirdrop(outsider.publicKey, anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+      outsiderTokenAccount = await createAssociatedTokenAccount(connection, admin, mint, outsider.publicKey);
+      adminTokenAccount = await createAccount(connection, admin, mint, admin.publicKey, Keypair.generate());
+      await mintTo(connection, admin, mint, outsiderTokenAccount, admin, DEPOSIT * 4);
+      await mintTo(connection, admin, mint, adminTokenAccount, admin, DEPOSIT * 4);
+    });
+
+    after(async () => {
+      await setOpen(true);
+    });
+
+    it("should default to open deposits", async () => {
+      const fund = await program.account.fundAccount.fetch(fundAccount);
+      expect(fund.openDeposits).to.be.true;
+
+      await depositAs(outsider, outsiderTokenAccount);
+    });
+
+    it("should emit an event when deposits are closed", async () => {
+      const signature = await setOpen(false);
+
+      const event = await findEvent(signature, "OpenDepositsUpdated");
+      expect(event.data.openDeposits).to.be.false;
+      expect(event.data.actor.toString()).to.equal(admin.publicKey.toString());
+    });
+
+    it("should reject a third-party deposit while closed", async () => {
+      try {
+        await depositAs(outsider, outsiderTokenAccount);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DepositsRestricted");
+      }
+    });
+
+    it("should still accept an admin deposit while closed", async () => {
+      const before = await program.account.fundAccount.fetch(fundAccount);
+      await depositAs(admin, adminTokenAccount);
+      const after = await program.account.fundAccount.fetch(fundAccount);
+      expect(after.totalFunds.sub(before.totalFunds).toNumber()).to.equal(DEPOSIT);
+    });
+  });
+
+  describe("Whitelist Recount", () => {
+    const recountFundId = newFundId();
+    const members = Array.from({ length: 3 }, () => Keypair.generate());
+    let recountFund: PublicKey;
+    let entries: PublicKey[];
+
+    const recount = (reset: boolean, keys: PublicKey[]) =>
+      program.methods
+        .recountWhitelist(reset)
+        .accounts({
+          fundAccount: recountFund,
+          admin: admin.publicKey,
+        })
+        .remainingAccounts(keys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      const connection = provider.connection;
+      const recountMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      [recountFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(recountFundId)],
+        program.programId
+      );
+      const recountVault = await createAccount(connection, admin, recountMint, recountFund, Keypair.generate());
+
+      await program.methods
+        .initialize(recountFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: recountFund,
+          mint: recountMint,
+          fundTokenAccount: recountVault,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      entries = members.map(
+        (member) =>
+          PublicKey.findProgramAddressSync(
+            [Buffer.from("whitelist"), Buffer.from(recountFundId), member.publicKey.toBuffer()],
+            program.programId
+          )[0]
+      );
+      await program.methods
+        .addWhitelistBatch(members.map((member, i) => ({ address: member.publicKey, label: `Member ${i}` })))
+        .accounts({
+          fundAccount: recountFund,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(entries.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .removeWhitelist()
+        .accounts({
+          fundAccount: recountFund,
+          whitelistEntry: entries[0],
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should keep active and lifetime counts apart", async () => {
+      const fund = await program.account.fundAccount.fetch(recountFund);
+      expect(fund.whitelistActive).to.equal(2);
+      expect(fund.whitelistTotalCreated).to.equal(3);
+    });
+
+    it("should rebuild both counters from the passed entries", async () => {
+      await recount(true, entries.slice(0, 2));
+      const signature = await recount(false, entries.slice(2));
+
+      const event = await findEvent(signature, "WhitelistRecounted");
+      expect(event.data.whitelistActive).to.equal(2);
+      expect(event.data.whitelistTotalCreated).to.equal(3);
+
+      const fund = await program.account.fundAccount.fetch(recountFund);
+      expect(fund.whitelistActive).to.equal(2);
+      expect(fund.whitelistTotalCreated).to.equal(3);
+    });
+
+    it("should reject an entry listed twice", async () => {
+      try {
+        await recount(true, [entries[1], entries[1]]);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DuplicateBatchAddress");
+      }
+    });
+
+    it("should reject entries from another fund", async () => {
+      const stranger = Keypair.generate();
+      const [foreignEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), stranger.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .addWhitelist(stranger.publicKey, "Primary Fund Entry", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry: foreignEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await recount(false, [foreignEntry]);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistFundMismatch");
+      }
+    });
+  });
+
+  describe("Whitelist Cleanup", () => {
+    let recipient: Keypair;
+    let whitelistEntry: PublicKey;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Closable Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    const closeWhitelist = () =>
+      program.methods
+        .closeWhitelist()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    it("should refuse to close an active entry", async () => {
+      try {
+        await closeWhitelist();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistEntryStillActive");
+      }
+    });
+
+    it("should decrement whitelist_count when an entry is removed", async () => {
+      const countBefore = (await program.account.fundAccount.fetch(fundAccount)).whitelistActive;
+
+      await program.methods
+        .removeWhitelist()
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAccountData.whitelistActive).to.equal(countBefore - 1);
+    });
+
+    it("should close an inactive entry and refund rent to the admin", async () => {
+      const rent = await provider.connection.getBalance(whitelistEntry);
+      const adminBalanceBefore = await provider.connection.getBalance(admin.publicKey);
+      const countBefore = (await program.account.fundAccount.fetch(fundAccount)).whitelistActive;
+
+      await closeWhitelist();
+
+      const adminBalanceAfter = await provider.connection.getBalance(admin.publicKey);
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+
+      expect(await provider.connection.getAccountInfo(whitelistEntry)).to.be.null;
+      expect(adminBalanceAfter).to.be.greaterThan(adminBalanceBefore + rent - 10_000);
+      expect(fundAccountData.whitelistActive).to.equal(countBefore);
+    });
+  });
+
+  describe("Emergency Withdrawal", () => {
+    const EMERGENCY_AMOUNT = 1_000_000; // 1 token
+    let recoveryTokenAccount: PublicKey;
+
+    before(async () => {
+      recoveryTokenAccount = await createAssociatedTokenAccount(provider.connection, admin, mint, recovery.publicKey);
+    });
+
+    const emergencyWithdraw = (signer: Keypair, destination: PublicKey) =>
+      program.methods
+        .emergencyWithdraw(new anchor.BN(EMERGENCY_AMOUNT))
+        .accounts({
+          fundAccount,
+          mint,
+          fundTokenAccount,
+          recoveryTokenAccount: destination,
+          admin: signer.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc({ commitment: "confirmed" });
+
+    it("should let the admin evacuate funds to the recovery address", async () => {
+      const signature = await emergencyWithdraw(admin, recoveryTokenAccount);
+
+      const balance = await getAccount(provider.connection, recoveryTokenAccount);
+      expect(Number(balance.amount)).to.equal(EMERGENCY_AMOUNT);
+
+      const event = await findEvent(signature, "EmergencyWithdrawal");
+      expect(event.data.recoveryAddress.toString()).to.equal(recovery.publicKey.toString());
+      expect(event.data.amount.toNumber()).to.equal(EMERGENCY_AMOUNT);
+    });
+
+    it("should refuse any destination other than the recovery address", async () => {
+      try {
+        await emergencyWithdraw(admin, user1TokenAccount);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidRecoveryAddress");
+      }
+    });
+
+    it("should refuse non-admin signers", async () => {
+      try {
+        await emergencyWithdraw(user1, recoveryTokenAccount);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Emergency Drain", () => {
+    const DRAIN_DEPOSIT = 5_000_000;
+    const drainFundId = newFundId();
+    let drainMint: PublicKey;
+    let drainFundAccount: PublicKey;
+    let drainFundTokenAccount: PublicKey;
+    let drainRecoveryAccount: PublicKey;
+    let depositorAccount: PublicKey;
+
+    const setDrainPaused = (paused: boolean) =>
+      program.methods
+        .setPaused(paused)
+        .accounts({
+          fundAccount: drainFundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+    const drain = () =>
+      program.methods
+        .emergencyDrain()
+        .accounts({
+          fundAccount: drainFundAccount,
+          mint: drainMint,
+          fundTokenAccount: drainFundTokenAccount,
+          recoveryTokenAccount: drainRecoveryAccount,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    // Drains a dedicated fund so the shared one keeps its balance for later suites
+    before(async () => {
+      const connection = provider.connection;
+      drainMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      [drainFundAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(drainFundId)],
+        program.programId
+      );
+      drainFundTokenAccount = await createAccount(connection, admin, drainMint, drainFundAccount, Keypair.generate());
+      drainRecoveryAccount = await createAssociatedTokenAccount(connection, admin, drainMint, recovery.publicKey);
+      depositorAccount = await createAssociatedTokenAccount(connection, admin, drainMint, user1.publicKey);
+      await mintTo(connection, admin, drainMint, depositorAccount, admin, DRAIN_DEPOSIT);
+
+      await program.methods
+        .initialize(drainFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: drainFundAccount,
+          mint: drainMint,
+          fundTokenAccount: drainFundTokenAccount,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .storeFunds(new anchor.BN(DRAIN_DEPOSIT))
+        .accounts({
+          fundAccount: drainFundAccount,
+          mint: drainMint,
+          fromTokenAccount: depositorAccount,
+          fundTokenAccount: drainFundTokenAccount,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+    });
+
+    it("should refuse to drain while the fund is not paused", async () => {
+      try {
+        await drain();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundNotPaused");
+      }
+    });
+
+    it("should refuse to drain while claims are outstanding", async () => {
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(drainFundId), user2.publicKey.toBuffer()],
+        program.programId
+      );
+      const [claim] = PublicKey.findProgramAddressSync(
+        [Buffer.from("claim"), drainFundAccount.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .addWhitelist(user2.publicKey, "Drain Claimant", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount: drainFundAccount,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .createClaim(new anchor.BN(1_000_000))
+        .accounts({
+          fundAccount: drainFundAccount,
+          whitelistEntry,
+          claim,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await setDrainPaused(true);
+
+      try {
+        await drain();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ClaimsOutstanding");
+      }
+
+      // Cancelling the claim returns its earmark to the pool
+      await program.methods
+        .setClaimGracePeriod(new anchor.BN(0))
+        .accounts({
+          fundAccount: drainFundAccount,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .cancelClaim()
+        .accounts({
+          fundAccount: drainFundAccount,
+          whitelistEntry,
+          claim,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should move the whole vault to the recovery address once paused", async () => {
+      await setDrainPaused(true);
+      const signature = await drain();
+
+      const vault = await getAccount(provider.connection, drainFundTokenAccount);
+      const rescued = await getAccount(provider.connection, drainRecoveryAccount);
+      expect(Number(vault.amount)).to.equal(0);
+      expect(Number(rescued.amount)).to.equal(DRAIN_DEPOSIT);
+
+      const fund = await program.account.fundAccount.fetch(drainFundAccount);
+      expect(fund.totalFunds.toNumber()).to.equal(0);
+
+      const event = await findEvent(signature, "EmergencyDrained");
+      expect(event.data.fund.toString()).to.equal(drainFundAccount.toString());
+      expect(event.data.amount.toNumber()).to.equal(DRAIN_DEPOSIT);
+    });
+
+    it("should refuse a depositor withdrawal the drained pool cannot cover", async () => {
+      await setDrainPaused(false);
+
+      const [depositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), drainFundAccount.toBuffer(), user1.publicKey.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .withdrawDeposit(new anchor.BN(DRAIN_DEPOSIT))
+          .accounts({
+            fundAccount: drainFundAccount,
+            mint: drainMint,
+            fundTokenAccount: drainFundTokenAccount,
+            depositor,
+            destinationTokenAccount: depositorAccount,
+            authority: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientFunds");
+      }
+    });
+
+    it("should let the admin close a depositor record the drained pool cannot repay", async () => {
+      const [depositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), drainFundAccount.toBuffer(), user1.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const rent = (await provider.connection.getAccountInfo(depositor)).lamports;
+      const walletBefore = await provider.connection.getBalance(user1.publicKey);
+
+      // The record's rent goes back to the depositor who paid for it
+      await program.methods
+        .closeDepositor()
+        .accounts({
+          fundAccount: drainFundAccount,
+          depositor,
+          depositorWallet: user1.publicKey,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      expect(await provider.connection.getAccountInfo(depositor)).to.be.null;
+      expect(await provider.connection.getBalance(user1.publicKey)).to.equal(walletBefore + rent);
+    });
+  });
+
+  describe("Close Fund", () => {
+    const STRAY_AMOUNT = 1_234;
+
+    it("should refuse to close a fund that still holds funds", async () => {
+      const [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), fundAccount.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .closeFund()
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            allocationLog,
+            destinationTokenAccount: null,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FundNotEmpty");
+      }
+    });
+
+    it("should sweep the remainder and close every fund account", async () => {
+      const connection = provider.connection;
+      const closeMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      const closeFundId = newFundId();
+      const [closeFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(closeFundId)],
+        program.programId
+      );
+      const [allocationLog] = PublicKey.findProgramAddressSync(
+        [Buffer.from("allocation_log"), closeFund.toBuffer()],
+        program.programId
+      );
+      const closeVault = await createAccount(connection, admin, closeMint, closeFund, Keypair.generate());
+      const destination = await createAssociatedTokenAccount(connection, admin, closeMint, admin.publicKey);
+
+      await program.methods
+        .initialize(closeFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: closeFund,
+          mint: closeMint,
+          fundTokenAccount: closeVault,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      // Sent straight to the vault, so total_funds stays at zero
+      await mintTo(connection, admin, closeMint, closeVault, admin, STRAY_AMOUNT);
+
+      // A removed entry still holds rent that only close_whitelist can reclaim
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(closeFundId), user2.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .addWhitelist(user2.publicKey, "Departing Vendor", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount: closeFund,
+          whitelistEntry,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      await program.methods
+        .removeWhitelist()
+        .accounts({
+          fundAccount: closeFund,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      try {
+        await program.methods
+          .closeFund()
+          .accounts({
+            fundAccount: closeFund,
+            mint: closeMint,
+            fundTokenAccount: closeVault,
+            allocationLog,
+            destinationTokenAccount: destination,
+            admin: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("WhitelistNotEmpty");
+      }
+
+      await program.methods
+        .closeWhitelist()
+        .accounts({
+          fundAccount: closeFund,
+          whitelistEntry,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const signature = await program.methods
+        .closeFund()
+        .accounts({
+          fundAccount: closeFund,
+          mint: closeMint,
+          fundTokenAccount: closeVault,
+          allocationLog,
+          destinationTokenAccount: destination,
+          admin: admin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const swept = await getAccount(connection, destination);
+      expect(Number(swept.amount)).to.equal(STRAY_AMOUNT);
+      expect(await connection.getAccountInfo(closeFund)).to.be.null;
+      expect(await connection.getAccountInfo(closeVault)).to.be.null;
+      expect(await connection.getAccountInfo(allocationLog)).to.be.null;
+
+      const event = await findEvent(signature, "FundClosed");
+      expect(event.data.swept.toNumber()).to.equal(STRAY_AMOUNT);
+    });
+  });
+
+  describe("Admin Timelock", () => {
+    const DELAY_SECS = 3;
+    const timelockFundId = newFundId();
+    const successor = Keypair.generate();
+    let timelockFund: PublicKey;
+
+    const setDelay = (delaySecs: number) =>
+      program.methods
+        .setAdminChangeDelay(new anchor.BN(delaySecs))
+        .accounts({
+          fundAccount: timelockFund,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const propose = (current: Keypair, proposed: PublicKey) =>
+      program.methods
+        .proposeAdmin(proposed)
+        .accounts({
+          fundAccount: timelockFund,
+          currentAdmin: current.publicKey,
+        })
+        .signers([current])
+        .rpc({ commitment: "confirmed" });
+
+    const accept = (proposed: Keypair) =>
+      program.methods
+        .acceptAdmin()
+        .accounts({
+          fundAccount: timelockFund,
+          newAdmin: proposed.publicKey,
+        })
+        .signers([proposed])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      const connection = provider.connection;
+      const timelockMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      [timelockFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(timelockFundId)],
+        program.programId
+      );
+      const timelockVault = await createAccount(connection, admin, timelockMint, timelockFund, Keypair.generate());
+
+      await program.methods
+        .initialize(timelockFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: timelockFund,
+          mint: timelockMint,
+          fundTokenAccount: timelockVault,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    });
+
+    it("should hold a scheduled admin change until the delay has passed", async () => {
+      await setDelay(DELAY_SECS);
+
+      const signature = await propose(admin, successor.publicKey);
+      const event = await findEvent(signature, "AdminChangeScheduled");
+      expect(event.data.newAdmin.toString()).to.equal(successor.publicKey.toString());
+      expect(event.data.effectiveAt.toNumber()).to.equal(event.data.timestamp.toNumber() + DELAY_SECS);
+
+      try {
+        await accept(successor);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AdminChangeTimelocked");
+      }
+    });
+
+    it("should let the current admin cancel during the delay", async () => {
+      const signature = await program.methods
+        .cancelAdminProposal()
+        .accounts({
+          fundAccount: timelockFund,
+          currentAdmin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "AdminChangeCancelled");
+      expect(event.data.cancelledAdmin.toString()).to.equal(successor.publicKey.toString());
+
+      const fund = await program.account.fundAccount.fetch(timelockFund);
+      expect(fund.pendingAdmin).to.be.null;
+      expect(fund.adminChangeEffectiveAt.toNumber()).to.equal(0);
+    });
+
+    it("should make a lowered delay wait out the current one", async () => {
+      const delaySignature = await setDelay(0);
+      const delayEvent = await findEvent(delaySignature, "AdminChangeDelayUpdated");
+      expect(delayEvent.data.oldDelaySecs.toNumber()).to.equal(DELAY_SECS);
+      expect(delayEvent.data.newDelaySecs.toNumber()).to.equal(0);
+
+      // Still scheduled under the old delay
+      const signature = await propose(admin, successor.publicKey);
+      const event = await findEvent(signature, "AdminChangeScheduled");
+      expect(event.data.effectiveAt.toNumber()).to.equal(event.data.timestamp.toNumber() + DELAY_SECS);
+
+      await waitForChainTime(event.data.effectiveAt.toNumber());
+      const acceptSignature = await accept(successor);
+      const finalized = await findEvent(acceptSignature, "AdminChangeFinalized");
+      expect(finalized.data.previousAdmin.toString()).to.equal(admin.publicKey.toString());
+
+      const fund = await program.account.fundAccount.fetch(timelockFund);
+      expect(fund.admin.toString()).to.equal(successor.publicKey.toString());
+    });
+
+    it("should rotate immediately once the delay is zero", async () => {
+      await propose(successor, admin.publicKey);
+      await accept(admin);
+
+      const fund = await program.account.fundAccount.fetch(timelockFund);
+      expect(fund.admin.toString()).to.equal(admin.publicKey.toString());
+      expect(fund.adminChangeDelaySecs.toNumber()).to.equal(0);
+    });
+  });
+
+  describe("Admin Management", () => {
+    it("should allow current admin to cancel an admin proposal", async () => {
+      await program.methods
+        .proposeAdmin(user2.publicKey)
+        .accounts({
+          fundAccount,
+          currentAdmin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .cancelAdminProposal()
+        .accounts({
+          fundAccount,
+          currentAdmin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAccountData.admin.toString()).to.equal(admin.publicKey.toString());
+      expect(fundAccountData.pendingAdmin).to.be.null;
+    });
+
+    it("should allow current admin to propose new admin", async () => {
+      await program.methods
+        .proposeAdmin(newAdmin.publicKey)
+        .accounts({
+          fundAccount,
+          currentAdmin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc();
+
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAccountData.admin.toString()).to.equal(admin.publicKey.toString());
+      expect(fundAccountData.pendingAdmin.toString()).to.equal(newAdmin.publicKey.toString());
+    });
+
+    it("should fail when someone other than the pending admin accepts", async () => {
+      try {
+        await program.methods
+          .acceptAdmin()
+          .accounts({
+            fundAccount,
+            newAdmin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedPendingAdmin");
+      }
+    });
+
+    it("should allow pending admin to accept", async () => {
+      await program.methods
+        .acceptAdmin()
+        .accounts({
+          fundAccount,
+          newAdmin: newAdmin.publicKey,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const fundAccountData = await program.account.fundAccount.fetch(fundAccount);
+      expect(fundAccountData.admin.toString()).to.equal(newAdmin.publicKey.toString());
+      expect(fundAccountData.pendingAdmin).to.be.null;
+    });
+
+    it("should allow new admin to allocate funds", async () => {
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipient.publicKey
+      );
+      
+      // Find whitelist entry PDA for new recipient
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      // Add recipient to whitelist using new admin
+      await program.methods
+        .addWhitelist(recipient.publicKey, "New Admin Test Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: newAdmin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      const remainingFunds = (await program.account.fundAccount.fetch(fundAccount)).totalFunds.toNumber();
+      const allocationAmount = Math.min(10_000_000, remainingFunds); // 10 tokens or remaining funds
+
+      if (allocationAmount > 0) {
+        await program.methods
+          .allocateFunds(new anchor.BN(allocationAmount))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: newAdmin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([newAdmin])
+          .rpc();
+
+        const recipientBalance = await getAccount(provider.connection, recipientAccount);
+        expect(Number(recipientBalance.amount)).to.equal(allocationAmount);
+      }
+    });
+
+    it("should fail when old admin tries to allocate funds", async () => {
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipient.publicKey
+      );
+      
+      // Find whitelist entry PDA
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      // Add recipient to whitelist using current admin (newAdmin)
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Old Admin Test Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: newAdmin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(1_000_000))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: admin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([admin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAllocator");
+      }
+    });
+
+    it("should fail when non-admin tries to propose new admin", async () => {
+      try {
+        await program.methods
+          .proposeAdmin(user1.publicKey)
+          .accounts({
+            fundAccount,
+            currentAdmin: user1.publicKey,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAdmin");
+      }
+    });
+  });
+
+  describe("Edge Cases", () => {
+    it("should reject zero amount deposits", async () => {
+      try {
+        await program.methods
+          .storeFunds(new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            mint,
+            fromTokenAccount: user1TokenAccount,
+            fundTokenAccount,
+            authority: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ZeroAmount");
+      }
+    });
+
+    it("should reject zero amount allocations", async () => {
+      const recipient = Keypair.generate();
+      const recipientAccount = await createAssociatedTokenAccount(
+        provider.connection,
+        admin,
+        mint,
+        recipient.publicKey
+      );
+      
+      // Find whitelist entry PDA
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from("whitelist"), Buffer.from(fundId), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      
+      // Add recipient to whitelist
+      await program.methods
+        .addWhitelist(recipient.publicKey, "Zero Amount Test Recipient", new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          fundAccount,
+          whitelistEntry,
+          admin: newAdmin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([newAdmin])
+        .rpc();
+
+      try {
+        await program.methods
+          .allocateFunds(new anchor.BN(0))
+          .accounts({
+            fundAccount,
+            mint,
+            fundTokenAccount,
+            toTokenAccount: recipientAccount,
+            whitelistEntry,
+            authority: newAdmin.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([newAdmin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ZeroAmount");
+      }
+    });
+
+    it("should return MathOverflow instead of panicking when the lifetime deposit total would overflow", async () => {
+      const U64_MAX = new anchor.BN("18446744073709551615");
+      const connection = provider.connection;
+      const bigFundId = newFundId();
+      const [bigFundAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("fund_account"), Buffer.from(bigFundId)],
+        program.programId
+      );
+      const [bigDepositor] = PublicKey.findProgramAddressSync(
+        [Buffer.from("depositor"), bigFundAccount.toBuffer(), user1.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // A fresh mint lets the depositor hold u64::MAX tokens
+      const bigMint = await createMint(connection, admin, admin.publicKey, null, 0);
+      const bigFundTokenAccount = await createAccount(connection, admin, bigMint, bigFundAccount, Keypair.generate());
+      const bigUserAccount = await createAssociatedTokenAccount(connection, admin, bigMint, user1.publicKey);
+      await mintTo(connection, admin, bigMint, bigUserAccount, admin, BigInt(U64_MAX.toString()));
+
+      await program.methods
+        .initialize(bigFundId, admin.publicKey, recovery.publicKey)
+        .accounts({
+          fundAccount: bigFundAccount,
+          mint: bigMint,
+          fundTokenAccount: bigFundTokenAccount,
+          payer: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      const deposit = (amount: anchor.BN) =>
+        program.methods
+          .storeFunds(amount)
+          .accounts({
+            fundAccount: bigFundAccount,
+            mint: bigMint,
+            fromTokenAccount: bigUserAccount,
+            fundTokenAccount: bigFundTokenAccount,
+            authority: user1.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([user1])
+          .rpc();
+
+      // total_funds drops back to 0 on withdrawal, but the lifetime total_deposited stays at u64::MAX
+      await deposit(U64_MAX);
+      await program.methods
+        .withdrawDeposit(U64_MAX)
+        .accounts({
+          fundAccount: bigFundAccount,
+          mint: bigMint,
+          fundTokenAccount: bigFundTokenAccount,
+          depositor: bigDepositor,
+          destinationTokenAccount: bigUserAccount,
+          authority: user1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([user1])
+        .rpc();
+
+      try {
+        await deposit(new anchor.BN(1));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("MathOverflow");
+      }
+
+      const fund = await program.account.fundAccount.fetch(bigFundAccount);
+      expect(fund.totalDeposited.toString()).to.equal(U64_MAX.toString());
+      expect(fund.totalFunds.toNumber()).to.equal(0);
+    });
+  });
+});
+
+describe("Instance Registry", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const program = anchor.workspace.InstanceRegistry as Program<InstanceRegistry>;
+
+  const REGISTRATION_FEE = anchor.web3.LAMPORTS_PER_SOL / 10;
+
+  let registryAdmin: Keypair;
+  let operator: Keypair;
+  let registry: PublicKey;
+  let endpointCounter = 0;
+
+  const eventParser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+
+  const findEvent = async (signature: string, name: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const events = [...eventParser.parseLogs(tx.meta.logMessages)];
+    return events.find((event) => event.name.toLowerCase() === name.toLowerCase());
+  };
+
+  const pda = (seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const instanceAddress = (instanceId: number[]) => pda([Buffer.from("instance"), Buffer.from(instanceId)]);
+  const rateLimitAddress = (authority: PublicKey) => pda([Buffer.from("rate_limit"), authority.toBuffer()]);
+  const blockedAddress = (authority: PublicKey) => pda([Buffer.from("blocked"), authority.toBuffer()]);
+  // Endpoints exceed the seed length limit, so claims are seeded by their sha256
+  const endpointClaimAddress = (endpoint: string) =>
+    pda([Buffer.from("endpoint"), createHash("sha256").update(endpoint).digest()]);
+
+  const newInstanceId = () => Array.from(Keypair.generate().publicKey.toBytes());
+  const newEndpoint = () => `https://node-${++endpointCounter}.example.com`;
+
+  const register = async (authority: Keypair, initialHeartbeat: number | null = null, endpoint = newEndpoint()) => {
+    const instanceId = newInstanceId();
+    const instance = instanceAddress(instanceId);
+    const signature = await program.methods
+      .registerInstance(
+        instanceId,
+        endpoint,
+        Array(32).fill(7),
+        initialHeartbeat === null ? null : new anchor.BN(initialHeartbeat)
+      )
+      .accounts({
+        registry,
+        instance,
+        rateLimit: rateLimitAddress(authority.publicKey),
+        blocked: blockedAddress(authority.publicKey),
+        endpointClaim: endpointClaimAddress(endpoint),
+        feeMint: null,
+        feeSource: null,
+        feeVault: null,
+        authority: authority.publicKey,
+        tokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+    return { instanceId, instance, endpoint, signature };
+  };
+
+  const deactivate = (instance: PublicKey, authority: Keypair) =>
+    program.methods
+      .deactivateInstance()
+      .accounts({
+        registry,
+        instance,
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+
+  const reactivate = async (instance: PublicKey, authority: Keypair) => {
+    const record = await program.account.instanceRecord.fetch(instance);
+    return program.methods
+      .reactivateInstance()
+      .accounts({
+        registry,
+        instance,
+        blocked: blockedAddress(record.authority),
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+  };
+
+  const closeInstance = (instance: PublicKey, endpoint: string, authority: Keypair) =>
+    program.methods
+      .closeInstance()
+      .accounts({
+        registry,
+        instance,
+        rateLimit: rateLimitAddress(authority.publicKey),
+        endpointClaim: endpointClaimAddress(endpoint),
+        authority: authority.publicKey,
+      })
+      .signers([authority])
+      .rpc({ commitment: "confirmed" });
+
+  const setRefundBps = (refundBps: number) =>
+    program.methods
+      .updateRefundBps(refundBps)
+      .accounts({
+        registry,
+        admin: registryAdmin.publicKey,
+      })
+      .signers([registryAdmin])
+      .rpc();
+
+  const updateRegistry = () => ({
+    registry,
+    admin: registryAdmin.publicKey,
+  });
+
+  const setHeartbeatTimeout = (seconds: number) =>
+    program.methods
+      .updateHeartbeatTimeout(new anchor.BN(seconds))
+      .accounts(updateRegistry())
+      .signers([registryAdmin])
+      .rpc();
+
+  const setLivenessPolicy = (gracePeriod: number, missedHeartbeatThreshold: number) =>
+    program.methods
+      .updateLivenessPolicy(new anchor.BN(gracePeriod), missedHeartbeatThreshold)
+      .accounts(updateRegistry())
+      .signers([registryAdmin])
+      .rpc();
+
+  const checkLiveness = (instance: PublicKey) =>
+    program.methods
+      .checkLiveness()
+      .accounts({
+        registry,
+        instance,
+      })
+      .rpc({ commitment: "confirmed" });
+
+  const lamports = async (key: PublicKey) => provider.connection.getBalance(key, "confirmed");
+
+  const chainTime = async () => {
+    const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY, "confirmed");
+    return Number(clock.data.readBigInt64LE(32));
+  };
+
+  const waitForChainTime = async (target: number) => {
+    while ((await chainTime()) < target) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+  };
+
+  before(async () => {
+    registryAdmin = Keypair.generate();
+    operator = Keypair.generate();
+    registry = pda([Buffer.from("registry_config")]);
+
+    await Promise.all(
+      [registryAdmin, operator].map(async (keypair) =>
+        provider.connection.confirmTransaction(
+          await provider.connection.requestAirdrop(keypair.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+          "confirmed"
+        )
+      )
+    );
+
+    // No per-registration gap, no reaping and no heartbeat throttling to start with
+    await program.methods
+      .initialize(new anchor.BN(REGISTRATION_FEE), new anchor.BN(0), new anchor.BN(0), new anchor.BN(0), new anchor.BN(0))
+      .accounts({
+        registry,
+        admin: registryAdmin.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([registryAdmin])
+      .rpc();
+  });
+
+  describe("Registration", () => {
+    it("should register an instance and collect the fee", async () => {
+      const registryBefore = await lamports(registry);
+
+      const { instanceId, instance, endpoint, signature } = await register(operator);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.authority.toString()).to.equal(operator.publicKey.toString());
+      expect(record.endpoint).to.equal(endpoint);
+      expect(record.status).to.deep.equal({ active: {} });
+      expect(record.feeRefunded).to.be.false;
+      expect(record.feePaid.toNumber()).to.equal(REGISTRATION_FEE);
+      expect(record.feeInTokens).to.be.false;
+      expect(await lamports(registry)).to.equal(registryBefore + REGISTRATION_FEE);
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.totalInstances.toNumber()).to.equal(1);
+      expect(config.activeInstances.toNumber()).to.equal(1);
+      expect(config.openInstances.toNumber()).to.equal(1);
+
+      const event = await findEvent(signature, "InstanceRegistered");
+      expect(event.data.instanceId).to.deep.equal(instanceId);
+    });
+
+    it("should reject an endpoint another instance already holds", async () => {
+      const { endpoint } = await register(operator);
+      const instanceId = newInstanceId();
+
+      try {
+        await program.methods
+          .registerInstance(instanceId, endpoint, Array(32).fill(0), null)
+          .accounts({
+            registry,
+            instance: instanceAddress(instanceId),
+            rateLimit: rateLimitAddress(operator.publicKey),
+            blocked: blockedAddress(operator.publicKey),
+            endpointClaim: endpointClaimAddress(endpoint),
+            feeMint: null,
+            feeSource: null,
+            feeVault: null,
+            authority: operator.publicKey,
+            tokenProgram: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("EndpointAlreadyRegistered");
+      }
+    });
+  });
+
+  describe("Deactivation Refunds", () => {
+    before(async () => {
+      await setRefundBps(5_000);
+    });
+
+    after(async () => {
+      await setRefundBps(0);
+    });
+
+    it("should refund part of the fee on a clean deactivation", async () => {
+      const { instance } = await register(operator);
+      const operatorBefore = await lamports(operator.publicKey);
+
+      const signature = await deactivate(instance, operator);
+
+      const event = await findEvent(signature, "InstanceDeactivated");
+      expect(event.data.refunded.toNumber()).to.equal(REGISTRATION_FEE / 2);
+      expect(await lamports(operator.publicKey)).to.equal(operatorBefore + REGISTRATION_FEE / 2);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ inactive: {} });
+      expect(record.feeRefunded).to.be.true;
+    });
+
+    it("should not refund again after a free reactivation", async () => {
+      const { instance } = await register(operator);
+      await deactivate(instance, operator);
+      const registryBefore = await lamports(registry);
+
+      // Looping deactivate/reactivate must not drain the collected fees
+      for (let i = 0; i < 3; i++) {
+        await reactivate(instance, operator);
+        const signature = await deactivate(instance, operator);
+
+        const event = await findEvent(signature, "InstanceDeactivated");
+        expect(event.data.refunded.toNumber()).to.equal(0);
+      }
+
+      expect(await lamports(registry)).to.equal(registryBefore);
+    });
+
+    it("should refund against the fee paid at registration, not the current fee", async () => {
+      const setFee = (fee: number) =>
+        program.methods
+          .updateRegistrationFee(new anchor.BN(fee))
+          .accounts(updateRegistry())
+          .signers([registryAdmin])
+          .rpc();
+
+      const { instance } = await register(operator);
+      await setFee(REGISTRATION_FEE * 4);
+
+      try {
+        const signature = await deactivate(instance, operator);
+
+        const event = await findEvent(signature, "InstanceDeactivated");
+        expect(event.data.refunded.toNumber()).to.equal(REGISTRATION_FEE / 2);
+      } finally {
+        await setFee(REGISTRATION_FEE);
+      }
+    });
+  });
+
+  describe("Registry Configuration", () => {
+    it("should emit RegistrationFeeUpdated when the fee changes", async () => {
+      const signature = await program.methods
+        .updateRegistrationFee(new anchor.BN(REGISTRATION_FEE * 2))
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "RegistrationFeeUpdated");
+      expect(event.data.oldFee.toNumber()).to.equal(REGISTRATION_FEE);
+      expect(event.data.newFee.toNumber()).to.equal(REGISTRATION_FEE * 2);
+
+      await program.methods
+        .updateRegistrationFee(new anchor.BN(REGISTRATION_FEE))
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc();
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.registrationFee.toNumber()).to.equal(REGISTRATION_FEE);
+    });
+
+    it("should reject a fee change from anyone but the admin", async () => {
+      try {
+        await program.methods
+          .updateRegistrationFee(new anchor.BN(0))
+          .accounts({
+            registry,
+            admin: operator.publicKey,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+    });
+  });
+
+  describe("Liveness", () => {
+    after(async () => {
+      await setHeartbeatTimeout(0);
+      await setLivenessPolicy(0, 1);
+    });
+
+    it("should start a warming-up instance at its initial heartbeat", async () => {
+      const initialHeartbeat = (await chainTime()) + 600;
+
+      const { instance } = await register(operator, initialHeartbeat);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.lastHeartbeat.toNumber()).to.equal(initialHeartbeat);
+      expect(record.registeredAt.toNumber()).to.be.lessThan(initialHeartbeat);
+    });
+
+    it("should reject an initial heartbeat in the past or beyond the warm-up", async () => {
+      const now = await chainTime();
+
+      for (const initialHeartbeat of [now - 600, now + 7_200]) {
+        try {
+          await register(operator, initialHeartbeat);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("InvalidInitialHeartbeat");
+        }
+      }
+    });
+
+    it("should deactivate a stale instance once it misses the threshold", async () => {
+      await setHeartbeatTimeout(1);
+      await setLivenessPolicy(0, 2);
+      const { instance } = await register(operator);
+      const { lastHeartbeat } = await program.account.instanceRecord.fetch(instance);
+      const activeBefore = (await program.account.registryConfig.fetch(registry)).activeInstances.toNumber();
+
+      await waitForChainTime(lastHeartbeat.toNumber() + 2);
+
+      // The first miss is only counted
+      let signature = await checkLiveness(instance);
+      let event = await findEvent(signature, "HeartbeatMissed");
+      expect(event.data.missedHeartbeats).to.equal(1);
+      let record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+
+      // The next miss counts one heartbeat_timeout later
+      await waitForChainTime(record.lastMissAt.toNumber() + 1);
+      signature = await checkLiveness(instance);
+      event = await findEvent(signature, "InstanceDeactivated");
+      expect(event.data.adminInitiated).to.be.false;
+      record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ inactive: {} });
+      expect(record.missedHeartbeats).to.equal(2);
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.activeInstances.toNumber()).to.equal(activeBefore - 1);
+    });
+
+    it("should count at most one miss per heartbeat interval", async () => {
+      const TIMEOUT = 2;
+      await setHeartbeatTimeout(TIMEOUT);
+      await setLivenessPolicy(0, 2);
+      const { instance } = await register(operator);
+      const { lastHeartbeat } = await program.account.instanceRecord.fetch(instance);
+
+      await waitForChainTime(lastHeartbeat.toNumber() + TIMEOUT + 1);
+
+      // Three checks in one transaction land in the same interval
+      const check = await program.methods.checkLiveness().accounts({ registry, instance }).instruction();
+      const signature = await program.methods
+        .checkLiveness()
+        .accounts({
+          registry,
+          instance,
+        })
+        .postInstructions([check, check])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await provider.connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const misses = [...eventParser.parseLogs(tx.meta.logMessages)].filter(
+        (event) => event.name.toLowerCase() === "heartbeatmissed"
+      );
+      expect(misses).to.have.length(1);
+
+      let record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+      expect(record.missedHeartbeats).to.equal(1);
+
+      // Only a full interval later does the second miss reach the threshold
+      await waitForChainTime(record.lastMissAt.toNumber() + TIMEOUT);
+      await checkLiveness(instance);
+      record = await program.account.instanceRecord.fetch(instance);
+      expect(record.missedHeartbeats).to.equal(2);
+      expect(record.status).to.deep.equal({ inactive: {} });
+    });
+
+    it("should leave a warming-up instance alone", async () => {
+      await setHeartbeatTimeout(1);
+      const { instance } = await register(operator, (await chainTime()) + 600);
+
+      await checkLiveness(instance);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+      expect(record.missedHeartbeats).to.equal(0);
+    });
+  });
+
+  describe("Reactivation", () => {
+    it("should reactivate a deactivated instance and refresh its heartbeat", async () => {
+      const { instance } = await register(operator);
+      await deactivate(instance, operator);
+      const activeBefore = (await program.account.registryConfig.fetch(registry)).activeInstances.toNumber();
+      const before = await chainTime();
+
+      const signature = await reactivate(instance, operator);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+      expect(record.lastHeartbeat.toNumber()).to.be.at.least(before);
+      expect(record.missedHeartbeats).to.equal(0);
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.activeInstances.toNumber()).to.equal(activeBefore + 1);
+
+      const event = await findEvent(signature, "InstanceReactivated");
+      expect(event.data.instanceId).to.deep.equal(record.instanceId);
+    });
+
+    it("should reject reactivation by another authority", async () => {
+      const { instance } = await register(operator);
+      await deactivate(instance, operator);
+
+      try {
+        await reactivate(instance, Keypair.generate());
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+    });
+
+    it("should reject reactivating an active instance", async () => {
+      const { instance } = await register(operator);
+
+      try {
+        await reactivate(instance, operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InstanceAlreadyActive");
+      }
+    });
+  });
+
+  describe("Token Fees", () => {
+    let feeMint: PublicKey;
+    let feeVault: PublicKey;
+    let operatorFeeAccount: PublicKey;
+
+    const registerWithTokenFee = async (vault: PublicKey) => {
+      const instanceId = newInstanceId();
+      const instance = instanceAddress(instanceId);
+      const endpoint = newEndpoint();
+      await program.methods
+        .registerInstance(instanceId, endpoint, Array(32).fill(7), null)
+        .accounts({
+          registry,
+          instance,
+          rateLimit: rateLimitAddress(operator.publicKey),
+          blocked: blockedAddress(operator.publicKey),
+          endpointClaim: endpointClaimAddress(endpoint),
+          feeMint,
+          feeSource: operatorFeeAccount,
+          feeVault: vault,
+          authority: operator.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([operator])
+        .rpc({ commitment: "confirmed" });
+      return instance;
+    };
+
+    before(async () => {
+      feeMint = await createMint(provider.connection, registryAdmin, registryAdmin.publicKey, null, 6);
+      feeVault = pda([Buffer.from("fee_vault"), feeMint.toBuffer()]);
+      operatorFeeAccount = await createAssociatedTokenAccount(provider.connection, registryAdmin, feeMint, operator.publicKey);
+      await mintTo(provider.connection, registryAdmin, feeMint, operatorFeeAccount, registryAdmin, REGISTRATION_FEE * 10);
+
+      const signature = await program.methods
+        .setFeeMint()
+        .accounts({
+          registry,
+          feeMint,
+          feeVault,
+          admin: registryAdmin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([registryAdmin])
+        .rpc({ commitment: "confirmed" });
+
+      const event = await findEvent(signature, "FeeMintUpdated");
+      expect(event.data.oldMint).to.be.null;
+      expect(event.data.newMint.toString()).to.equal(feeMint.toString());
+    });
+
+    after(async () => {
+      await program.methods
+        .clearFeeMint()
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc();
+    });
+
+    it("should collect the fee in tokens instead of lamports", async () => {
+      const registryBefore = await lamports(registry);
+      const vaultBefore = (await getAccount(provider.connection, feeVault, "confirmed")).amount;
+
+      const instance = await registerWithTokenFee(feeVault);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.feePaid.toNumber()).to.equal(REGISTRATION_FEE);
+      expect(record.feeInTokens).to.be.true;
+
+      const vaultAfter = (await getAccount(provider.connection, feeVault, "confirmed")).amount;
+      expect(Number(vaultAfter - vaultBefore)).to.equal(REGISTRATION_FEE);
+      expect(await lamports(registry)).to.equal(registryBefore);
+    });
+
+    it("should reject a registry-owned token account other than the fee vault", async () => {
+      // Same mint and authority as the vault, but not at the vault's address
+      const decoyVault = await createAccount(provider.connection, registryAdmin, feeMint, registry, Keypair.generate());
+
+      try {
+        await registerWithTokenFee(decoyVault);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("ConstraintSeeds");
+      }
+    });
+
+    it("should require the token accounts while a fee mint is set", async () => {
+      try {
+        await register(operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("FeeAccountsRequired");
+      }
+    });
+
+    it("should let the admin withdraw collected token fees", async () => {
+      const destination = await createAssociatedTokenAccount(provider.connection, registryAdmin, feeMint, registryAdmin.publicKey);
+      const vaultBefore = (await getAccount(provider.connection, feeVault, "confirmed")).amount;
+
+      await program.methods
+        .withdrawTokenFees(new anchor.BN(vaultBefore.toString()))
+        .accounts({
+          registry,
+          feeMint,
+          feeVault,
+          destination,
+          admin: registryAdmin.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([registryAdmin])
+        .rpc({ commitment: "confirmed" });
+
+      expect((await getAccount(provider.connection, feeVault, "confirmed")).amount).to.equal(BigInt(0));
+      expect((await getAccount(provider.connection, destination, "confirmed")).amount).to.equal(vaultBefore);
+    });
+  });
+
+  describe("Admin Deactivation", () => {
+    const adminDeactivate = (instance: PublicKey, admin: Keypair) =>
+      program.methods
+        .adminDeactivateInstance()
+        .accounts({
+          registry,
+          instance,
+          admin: admin.publicKey,
+        })
+        .signers([admin])
+        .rpc({ commitment: "confirmed" });
+
+    const setBlocked = (authority: PublicKey, blocked: boolean) =>
+      blocked
+        ? program.methods
+            .blockAuthority(authority)
+            .accounts({
+              registry,
+              blocked: blockedAddress(authority),
+              admin: registryAdmin.publicKey,
+              systemProgram: SystemProgram.programId,
+            })
+            .signers([registryAdmin])
+            .rpc()
+        : program.methods
+            .unblockAuthority()
+            .accounts({
+              registry,
+              blocked: blockedAddress(authority),
+              admin: registryAdmin.publicKey,
+            })
+            .signers([registryAdmin])
+            .rpc();
+
+    it("should let the admin deactivate an instance it does not own", async () => {
+      const { instance } = await register(operator);
+      const activeBefore = (await program.account.registryConfig.fetch(registry)).activeInstances.toNumber();
+
+      const signature = await adminDeactivate(instance, registryAdmin);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ inactive: {} });
+      expect(record.adminDeactivated).to.be.true;
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.activeInstances.toNumber()).to.equal(activeBefore - 1);
+
+      const event = await findEvent(signature, "InstanceDeactivated");
+      expect(event.data.adminInitiated).to.be.true;
+      expect(event.data.refunded.toNumber()).to.equal(0);
+    });
+
+    it("should keep the authority from reactivating an admin-deactivated instance", async () => {
+      const { instance } = await register(operator);
+      await adminDeactivate(instance, registryAdmin);
+
+      try {
+        await reactivate(instance, operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InstanceAdminDeactivated");
+      }
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ inactive: {} });
+    });
+
+    it("should reject admin deactivation from anyone but the admin", async () => {
+      const { instance } = await register(operator);
+
+      try {
+        await adminDeactivate(instance, operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+    });
+
+    it("should keep a blocked authority from reactivating its instances", async () => {
+      const { instance } = await register(operator);
+      await deactivate(instance, operator);
+      await setBlocked(operator.publicKey, true);
+
+      try {
+        await reactivate(instance, operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("AuthorityBlocked");
+      } finally {
+        await setBlocked(operator.publicKey, false);
+      }
+
+      await reactivate(instance, operator);
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+    });
+  });
+
+  describe("Descriptor Hash", () => {
+    const updateDescriptorHash = (instance: PublicKey, authority: Keypair, hash: number[]) =>
+      program.methods
+        .updateDescriptorHash(hash)
+        .accounts({
+          instance,
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    it("should record the registration hash and emit it", async () => {
+      const { instance, signature } = await register(operator);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.descriptorHash).to.deep.equal(Array(32).fill(7));
+
+      const event = await findEvent(signature, "InstanceRegistered");
+      expect(event.data.descriptorHash).to.deep.equal(Array(32).fill(7));
+    });
+
+    it("should let the authority replace the hash and emit both values", async () => {
+      const { instance } = await register(operator);
+      const newHash = Array.from(createHash("sha256").update("descriptor v2").digest());
+
+      const signature = await updateDescriptorHash(instance, operator, newHash);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.descriptorHash).to.deep.equal(newHash);
+
+      const event = await findEvent(signature, "DescriptorHashUpdated");
+      expect(event.data.oldHash).to.deep.equal(Array(32).fill(7));
+      expect(event.data.newHash).to.deep.equal(newHash);
+    });
+
+    it("should reject a hash update from another authority", async () => {
+      const { instance } = await register(operator);
+
+      try {
+        await updateDescriptorHash(instance, registryAdmin, Array(32).fill(1));
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.descriptorHash).to.deep.equal(Array(32).fill(7));
+    });
+  });
+
+  describe("Instance Closing", () => {
+    it("should close an instance and refund its rent to the authority", async () => {
+      const { instance, endpoint } = await register(operator);
+      const rent = (await lamports(instance)) + (await lamports(endpointClaimAddress(endpoint)));
+      const operatorBefore = await lamports(operator.publicKey);
+      const { activeCount } = await program.account.rateLimitAccount.fetch(rateLimitAddress(operator.publicKey));
+      const { activeInstances, totalInstances, openInstances } = await program.account.registryConfig.fetch(registry);
+
+      await closeInstance(instance, endpoint, operator);
+
+      expect(await provider.connection.getAccountInfo(instance)).to.be.null;
+      expect(await provider.connection.getAccountInfo(endpointClaimAddress(endpoint))).to.be.null;
+      expect(await lamports(operator.publicKey)).to.equal(operatorBefore + rent);
+
+      const rateLimit = await program.account.rateLimitAccount.fetch(rateLimitAddress(operator.publicKey));
+      expect(rateLimit.activeCount).to.equal(activeCount - 1);
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.activeInstances.toNumber()).to.equal(activeInstances.toNumber() - 1);
+      expect(config.openInstances.toNumber()).to.equal(openInstances.toNumber() - 1);
+      // total_instances counts registrations over the registry's lifetime
+      expect(config.totalInstances.toNumber()).to.equal(totalInstances.toNumber());
+    });
+
+    it("should not decrement the active count again for a deactivated instance", async () => {
+      const { instance, endpoint } = await register(operator);
+      await deactivate(instance, operator);
+      const { activeInstances } = await program.account.registryConfig.fetch(registry);
+
+      await closeInstance(instance, endpoint, operator);
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.activeInstances.toNumber()).to.equal(activeInstances.toNumber());
+    });
+
+    it("should free the endpoint for a new registration", async () => {
+      const { instance, endpoint } = await register(operator);
+      await closeInstance(instance, endpoint, operator);
+
+      const { instanceId, instance: successor } = await register(operator, null, endpoint);
+
+      const record = await program.account.instanceRecord.fetch(successor);
+      expect(record.endpoint).to.equal(endpoint);
+      const claim = await program.account.endpointClaim.fetch(endpointClaimAddress(endpoint));
+      expect(claim.instanceId).to.deep.equal(instanceId);
+    });
+
+    it("should free a max_instances slot when a record is closed", async () => {
+      const setMaxInstances = (maxInstances: number) =>
+        program.methods
+          .updateMaxInstances(new anchor.BN(maxInstances))
+          .accounts(updateRegistry())
+          .signers([registryAdmin])
+          .rpc();
+
+      const { openInstances } = await program.account.registryConfig.fetch(registry);
+      await setMaxInstances(openInstances.toNumber() + 1);
+
+      try {
+        const { instance, endpoint } = await register(operator);
+
+        try {
+          await register(operator);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("MaxInstancesReached");
+        }
+
+        await closeInstance(instance, endpoint, operator);
+        await register(operator);
+      } finally {
+        await setMaxInstances(0);
+      }
+    });
+  });
+
+  describe("Heartbeat Batches", () => {
+    const otherOperator = Keypair.generate();
+
+    const heartbeatBatch = (authority: Keypair, instances: PublicKey[]) =>
+      program.methods
+        .updateHeartbeatBatch()
+        .accounts({
+          registry,
+          authority: authority.publicKey,
+        })
+        .remainingAccounts(instances.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    before(async () => {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(otherOperator.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+    });
+
+    it("should refresh every instance in the batch", async () => {
+      const initialHeartbeat = (await chainTime()) + 600;
+      const { instance: first } = await register(operator, initialHeartbeat);
+      const { instance: second } = await register(operator, initialHeartbeat);
+
+      await heartbeatBatch(operator, [first, second]);
+
+      for (const instance of [first, second]) {
+        const record = await program.account.instanceRecord.fetch(instance);
+        expect(record.lastHeartbeat.toNumber()).to.be.lessThan(initialHeartbeat);
+      }
+    });
+
+    it("should fail the whole batch when one instance belongs to another authority", async () => {
+      const initialHeartbeat = (await chainTime()) + 600;
+      const { instance: own } = await register(operator, initialHeartbeat);
+      const { instance: foreign } = await register(otherOperator);
+
+      try {
+        await heartbeatBatch(operator, [own, foreign]);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+
+      // The instance ahead of the foreign one was not refreshed either
+      const record = await program.account.instanceRecord.fetch(own);
+      expect(record.lastHeartbeat.toNumber()).to.equal(initialHeartbeat);
+    });
+
+    it("should reject an instance listed twice", async () => {
+      const { instance } = await register(operator);
+
+      try {
+        await heartbeatBatch(operator, [instance, instance]);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("DuplicateBatchInstance");
+      }
+    });
+
+    it("should reject an empty or oversized batch", async () => {
+      // The size check runs before any account is loaded
+      const oversized = Array.from({ length: 21 }, () => Keypair.generate().publicKey);
+
+      for (const instances of [[], oversized]) {
+        try {
+          await heartbeatBatch(operator, instances);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("InvalidBatchSize");
+        }
+      }
+    });
+  });
+
+  describe("Registration Limits", () => {
+    const WINDOW_SECONDS = 3;
+    const windowOperator = Keypair.generate();
+
+    const setWindowLimit = (maxPerWindow: number, windowSeconds: number) =>
+      program.methods
+        .updateWindowLimit(maxPerWindow, new anchor.BN(windowSeconds))
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc();
+
+    const setMaxInstances = (maxInstances: number) =>
+      program.methods
+        .updateMaxInstances(new anchor.BN(maxInstances))
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc();
+
+    const setRegistrationsPaused = (paused: boolean) =>
+      program.methods
+        .setRegistrationsPaused(paused)
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc();
+
+    before(async () => {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(windowOperator.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL),
+        "confirmed"
+      );
+    });
+
+    it("should cap registrations per window and roll the window over", async () => {
+      await setWindowLimit(2, WINDOW_SECONDS);
+
+      try {
+        await register(windowOperator);
+        await register(windowOperator);
+
+        try {
+          await register(windowOperator);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("WindowLimitExceeded");
+        }
+
+        const { windowStart } = await program.account.rateLimitAccount.fetch(rateLimitAddress(windowOperator.publicKey));
+        await waitForChainTime(windowStart.toNumber() + WINDOW_SECONDS);
+        await register(windowOperator);
+
+        const rateLimit = await program.account.rateLimitAccount.fetch(rateLimitAddress(windowOperator.publicKey));
+        expect(rateLimit.windowStart.toNumber()).to.be.at.least(windowStart.toNumber() + WINDOW_SECONDS);
+        expect(rateLimit.registrationCount).to.equal(1);
+      } finally {
+        await setWindowLimit(0, 3_600);
+      }
+    });
+
+    it("should reject an empty rate window or a window change from anyone but the admin", async () => {
+      try {
+        await setWindowLimit(1, 0);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidRateWindow");
+      }
+
+      try {
+        await program.methods
+          .updateWindowLimit(1, new anchor.BN(WINDOW_SECONDS))
+          .accounts({
+            registry,
+            admin: operator.publicKey,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.maxPerWindow).to.equal(0);
+    });
+
+    it("should register up to max_instances and no further", async () => {
+      const { openInstances } = await program.account.registryConfig.fetch(registry);
+      await setMaxInstances(openInstances.toNumber() + 2);
+
+      try {
+        await register(operator);
+        await register(operator);
+
+        try {
+          await register(operator);
+          expect.fail("Should have thrown an error");
+        } catch (error) {
+          expect(error.message).to.include("MaxInstancesReached");
+        }
+      } finally {
+        await setMaxInstances(0);
+      }
+    });
+
+    it("should reject registrations while paused and accept them again once resumed", async () => {
+      await setRegistrationsPaused(true);
+
+      try {
+        await register(operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("RegistrationsPaused");
+      } finally {
+        await setRegistrationsPaused(false);
+      }
+
+      const { instance } = await register(operator);
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.status).to.deep.equal({ active: {} });
+    });
+
+    it("should only let the admin pause registrations", async () => {
+      try {
+        await program.methods
+          .setRegistrationsPaused(true)
+          .accounts({
+            registry,
+            admin: operator.publicKey,
+          })
+          .signers([operator])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("UnauthorizedAccess");
+      }
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(config.registrationsPaused).to.be.false;
+    });
+  });
+
+  describe("Endpoint Updates", () => {
+    const updateEndpoint = (instance: PublicKey, oldEndpoint: string, endpoint: string, authority: Keypair) =>
+      program.methods
+        .updateEndpoint(endpoint)
+        .accounts({
+          instance,
+          oldClaim: endpointClaimAddress(oldEndpoint),
+          newClaim: endpointClaimAddress(endpoint),
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([authority])
+        .rpc({ commitment: "confirmed" });
+
+    it("should move an instance to a new endpoint and release the old one", async () => {
+      const { instanceId, instance, endpoint } = await register(operator);
+      const nextEndpoint = newEndpoint();
+
+      const signature = await updateEndpoint(instance, endpoint, nextEndpoint, operator);
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.endpoint).to.equal(nextEndpoint);
+      expect(await provider.connection.getAccountInfo(endpointClaimAddress(endpoint))).to.be.null;
+      const claim = await program.account.endpointClaim.fetch(endpointClaimAddress(nextEndpoint));
+      expect(claim.instanceId).to.deep.equal(instanceId);
+
+      const event = await findEvent(signature, "EndpointUpdated");
+      expect(event.data.endpoint).to.equal(nextEndpoint);
+    });
+
+    it("should reject an endpoint another instance holds", async () => {
+      const { instance, endpoint } = await register(operator);
+      const { endpoint: taken } = await register(operator);
+
+      try {
+        await updateEndpoint(instance, endpoint, taken, operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("EndpointAlreadyRegistered");
+      }
+
+      const record = await program.account.instanceRecord.fetch(instance);
+      expect(record.endpoint).to.equal(endpoint);
+    });
+
+    it("should reject a malformed endpoint", async () => {
+      const { instance, endpoint } = await register(operator);
+
+      try {
+        await updateEndpoint(instance, endpoint, "ftp://node.example.com", operator);
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InvalidEndpoint");
+      }
+    });
+  });
+
+  describe("Stats and Fee Withdrawal", () => {
+    it("should report the registry counters through get_stats", async () => {
+      const stats = await program.methods.getStats().accounts({ registry }).view();
+
+      const config = await program.account.registryConfig.fetch(registry);
+      expect(stats.totalInstances.toNumber()).to.equal(config.totalInstances.toNumber());
+      expect(stats.activeInstances.toNumber()).to.equal(config.activeInstances.toNumber());
+      expect(stats.openInstances.toNumber()).to.equal(config.openInstances.toNumber());
+      expect(stats.registrationFee.toNumber()).to.equal(config.registrationFee.toNumber());
+      expect(stats.rateLimitSeconds.toNumber()).to.equal(config.rateLimitSeconds.toNumber());
+    });
+
+    it("should reject get_stats against an account that is not the registry", async () => {
+      const { instance } = await register(operator);
+
+      try {
+        await program.methods.getStats().accounts({ registry: instance }).rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.match(/AccountDiscriminatorMismatch|ConstraintSeeds/);
+      }
+    });
+
+    it("should let the admin withdraw collected fees", async () => {
+      const adminBefore = await lamports(registryAdmin.publicKey);
+      const registryBefore = await lamports(registry);
+
+      await program.methods
+        .withdrawFees(new anchor.BN(REGISTRATION_FEE))
+        .accounts(updateRegistry())
+        .signers([registryAdmin])
+        .rpc({ commitment: "confirmed" });
+
+      expect(await lamports(registry)).to.equal(registryBefore - REGISTRATION_FEE);
+      expect(await lamports(registryAdmin.publicKey)).to.equal(adminBefore + REGISTRATION_FEE);
+    });
+
+    it("should not withdraw into the registry's rent reserve", async () => {
+      const info = await provider.connection.getAccountInfo(registry, "confirmed");
+      const rentExemptMinimum = await provider.connection.getMinimumBalanceForRentExemption(info.data.length);
+      const available = info.lamports - rentExemptMinimum;
+
+      try {
+        await program.methods
+          .withdrawFees(new anchor.BN(available + 1))
+          .accounts(updateRegistry())
+          .signers([registryAdmin])
+          .rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.message).to.include("InsufficientRent");
+      }
+
+      expect(await lamports(registry)).to.equal(info.lamports);
+    });
+  });
+});
\ No newline at end of file